@@ -0,0 +1,58 @@
+//! parses a large synthetic log on 1, 2, 4 and 8 threads
+//!
+//! the log is `Q3A_BENCH_LOG_MB` megabytes big, 2048 if unset. the lines are kept in memory
+//! twice while parsing, so smaller sizes are better suited to machines with less memory:
+//!
+//! ```sh
+//! Q3A_BENCH_LOG_MB=256 cargo bench --bench parallel
+//! ```
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use q3a_log_parser::{parse_parallel, Collectors, Generator, MatchConfig, ScoringRules};
+
+/// games of about 15 minutes with 12 players, until the log reaches the given size
+fn synthetic_log(megabytes: usize) -> (Vec<String>, usize) {
+    let config = MatchConfig {
+        seed: 48,
+        games: usize::MAX,
+        players: 12,
+        ..MatchConfig::default()
+    };
+    let mut lines = Vec::new();
+    let mut bytes = 0;
+    for game in Generator::new(config) {
+        bytes += game.iter().map(|line| line.len() + 1).sum::<usize>();
+        lines.extend(game);
+        if bytes >= megabytes << 20 {
+            break;
+        }
+    }
+    (lines, bytes)
+}
+
+fn parallel(c: &mut Criterion) {
+    let megabytes = std::env::var("Q3A_BENCH_LOG_MB")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(2048);
+    let (lines, bytes) = synthetic_log(megabytes);
+    let scoring = ScoringRules::default();
+    let collectors = Collectors::builtin();
+
+    let mut group = c.benchmark_group("parse_parallel");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(bytes as u64));
+    for jobs in [1, 2, 4, 8] {
+        group.bench_function(format!("jobs_{}", jobs), |b| {
+            b.iter_batched(
+                || lines.clone(),
+                |lines| parse_parallel(lines, &scoring, &collectors, jobs).unwrap(),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parallel);
+criterion_main!(benches);
@@ -0,0 +1,160 @@
+//! builds the games of the `input` log and of a long synthetic session where players keep
+//! reconnecting and renaming, which is where looking players up and keeping the player lists
+//! up to date costs the most
+//!
+//! the player lists are also kept up to date on their own, both for just the player that
+//! changed and by building them again from every player, as games did before players were
+//! looked up by their slot
+
+use std::collections::HashMap;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use q3a_log_parser::parser::actions::parse_info;
+use q3a_log_parser::parser::game::TeamStats;
+use q3a_log_parser::{
+    generate, parse, parse_events, Action, MatchConfig, Players, ScoringRules, Team,
+};
+
+fn bench(c: &mut Criterion, name: &str, lines: Vec<String>, samples: usize) {
+    let scoring = ScoringRules::default();
+    let bytes: usize = lines.iter().map(|line| line.len() + 1).sum();
+
+    let mut group = c.benchmark_group("players");
+    group.sample_size(samples);
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function(name, |b| {
+        b.iter_batched(
+            || lines.clone(),
+            |lines| parse(lines, &scoring).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+/// a change to a player that shows on the game's lists
+enum Update {
+    Connect(u32),
+    Join(u32),
+    Rename(u32, String),
+    Team(u32, Team),
+}
+
+/// the changes made to the players of each game, in the order the log makes them
+fn updates(lines: Vec<String>) -> Vec<Vec<Update>> {
+    let update = |action: Action| match action {
+        Action::ClientConnect(id) => vec![Update::Connect(id)],
+        Action::ClientBegin(id) => vec![Update::Join(id)],
+        Action::ClientUserinfoChanged(id, info) => {
+            let mut updates = Vec::new();
+            if let Some(name) = info.split('\\').nth(1) {
+                updates.push(Update::Rename(id, name.to_string()));
+            }
+            let team = parse_info(&info)
+                .get("t")
+                .and_then(|team| team.parse::<u32>().ok())
+                .and_then(Team::from_u32);
+            if let Some(team) = team {
+                updates.push(Update::Team(id, team));
+            }
+            updates
+        }
+        _ => Vec::new(),
+    };
+    parse_events(lines)
+        .into_iter()
+        .map(|game| game.into_iter().flat_map(|e| update(e.action)).collect())
+        .collect()
+}
+
+/// applies the changes of a game, keeping its player list and team lists up to date after each
+/// one. `rebuild` builds them again from every player instead of placing just the one changed,
+/// the way games kept them before
+fn replay(updates: &[Update], rebuild: bool) -> Vec<String> {
+    let mut players = Players::new();
+    let mut player_list = Vec::new();
+    let mut teams: HashMap<Team, TeamStats> = HashMap::new();
+    for update in updates {
+        let id = match update {
+            Update::Connect(id) => {
+                players.connect(*id);
+                player_list.push(String::new());
+                continue;
+            }
+            Update::Join(id) | Update::Rename(id, _) | Update::Team(id, _) => *id,
+        };
+        let player = match players.slot_mut(id) {
+            Some(player) => player,
+            None => continue,
+        };
+        match update {
+            Update::Connect(_) => unreachable!(),
+            Update::Join(_) => player.joined = true,
+            Update::Rename(_, name) => player.name = name.clone(),
+            Update::Team(_, team) => player.change_team(*team),
+        }
+
+        if !rebuild {
+            players.update_lists(id, &mut player_list, &mut teams);
+            continue;
+        }
+        player_list.clear();
+        teams.values_mut().for_each(|team| team.players.clear());
+        for player in &players {
+            if player.joined && player.team != Team::Spectator {
+                player_list.push(player.name.clone());
+                if player.team.is_playing_side() {
+                    let team = teams.entry(player.team).or_default();
+                    team.players.push(player.name.clone());
+                }
+            }
+        }
+    }
+    player_list
+}
+
+fn lists(c: &mut Criterion, name: &str, lines: Vec<String>, samples: usize) {
+    let games = updates(lines);
+    for game in &games {
+        assert_eq!(replay(game, false), replay(game, true));
+    }
+    let count: usize = games.iter().map(|game| game.len()).sum();
+
+    let mut group = c.benchmark_group("players");
+    group.sample_size(samples);
+    group.throughput(Throughput::Elements(count as u64));
+    for (variant, rebuild) in [("incremental", false), ("rebuild", true)] {
+        group.bench_function(format!("{}_lists/{}", name, variant), |b| {
+            b.iter(|| {
+                for game in &games {
+                    replay(game, rebuild);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn players(c: &mut Criterion) {
+    let input = std::fs::read_to_string("input").unwrap();
+    let input: Vec<String> = input.lines().map(String::from).collect();
+    bench(c, "input", input.clone(), 100);
+    lists(c, "input", input, 100);
+
+    // 4 hours of 32 players with no fraglimit, each reconnecting every other minute
+    let session = generate(MatchConfig {
+        seed: 49,
+        players: 32,
+        duration: 4 * 60 * 60,
+        fraglimit: 0,
+        kills_per_minute: 60.0,
+        renames_per_minute: 0.5,
+        reconnects_per_minute: 0.5,
+        ..MatchConfig::default()
+    });
+    bench(c, "long_session", session.clone(), 10);
+    lists(c, "long_session", session, 10);
+}
+
+criterion_group!(benches, players);
+criterion_main!(benches);
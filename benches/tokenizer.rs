@@ -0,0 +1,32 @@
+//! reads the `input` log and a synthetic one into events, which is mostly cutting each line into
+//! its timestamp, keyword and payload
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use q3a_log_parser::{generate, parse_events, MatchConfig};
+
+fn bench(c: &mut Criterion, name: &str, lines: Vec<String>) {
+    let bytes: usize = lines.iter().map(|line| line.len() + 1).sum();
+
+    let mut group = c.benchmark_group("tokenizer");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function(name, |b| {
+        b.iter_batched(|| lines.clone(), parse_events, BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+fn tokenizer(c: &mut Criterion) {
+    let input = std::fs::read_to_string("input").unwrap();
+    bench(c, "input", input.lines().map(String::from).collect());
+
+    let synthetic = generate(MatchConfig {
+        seed: 50,
+        games: 20,
+        players: 12,
+        ..MatchConfig::default()
+    });
+    bench(c, "synthetic", synthetic);
+}
+
+criterion_group!(benches, tokenizer);
+criterion_main!(benches);
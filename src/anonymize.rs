@@ -0,0 +1,340 @@
+//! rewrites logs so they can be shared without exposing who played on them
+//!
+//! player names are replaced by pseudonyms everywhere they show up, the server's hostname and
+//! chat messages are scrubbed, and everything else is left untouched. the anonymized log parses
+//! into the same games, with the same statistics under the new names
+
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::line_type;
+
+/// what chat messages are replaced with
+pub const SCRUBBED_MESSAGE: &str = "<removed>";
+/// what the server's hostname is replaced with
+pub const SCRUBBED_HOSTNAME: &str = "Anonymous Server";
+
+/// anonymizes a log line by line, giving each name the same pseudonym every time it shows up
+///
+/// # Example
+/// ```
+/// use q3a_log_parser::anonymize::Anonymizer;
+///
+/// let mut anonymizer = Anonymizer::new();
+/// assert_eq!(
+///     anonymizer.line("  0:01 ClientUserinfoChanged: 2 n\\Isgalamido\\t\\0"),
+///     "  0:01 ClientUserinfoChanged: 2 n\\Player 1\\t\\0"
+/// );
+/// assert_eq!(
+///     anonymizer.line("  0:10 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT"),
+///     "  0:10 Kill: 1022 2 22: <world> killed Player 1 by MOD_TRIGGER_HURT"
+/// );
+/// assert_eq!(anonymizer.line("  0:12 say: Isgalamido: gg"), "  0:12 say: Player 1: <removed>");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Anonymizer {
+    pseudonyms: HashMap<String, String>,
+    /// pseudonyms given so far
+    assigned: HashSet<String>,
+    /// number of the next pseudonym to try
+    next: usize,
+    /// the name each client id currently has on the log, before being anonymized
+    clients: HashMap<u32, String>,
+}
+
+impl Anonymizer {
+    pub fn new() -> Anonymizer {
+        Anonymizer::default()
+    }
+
+    /// the pseudonym given to a name, picking the next one if the name wasn't seen yet
+    ///
+    /// pseudonyms that are already the name of a player seen so far are skipped, so a player
+    /// actually named `Player 2` isn't mistaken for someone else
+    pub fn pseudonym(&mut self, name: &str) -> String {
+        if name.is_empty() || name == "<world>" {
+            return name.to_string();
+        }
+        if let Some(pseudonym) = self.pseudonyms.get(name) {
+            return pseudonym.clone();
+        }
+        let pseudonym = loop {
+            self.next += 1;
+            let candidate = format!("Player {}", self.next);
+            if candidate != name
+                && !self.pseudonyms.contains_key(&candidate)
+                && !self.assigned.contains(&candidate)
+            {
+                break candidate;
+            }
+        };
+        self.assigned.insert(pseudonym.clone());
+        self.pseudonyms.insert(name.to_string(), pseudonym.clone());
+        pseudonym
+    }
+
+    /// every name seen so far and the pseudonym it was given
+    pub fn pseudonyms(&self) -> &HashMap<String, String> {
+        &self.pseudonyms
+    }
+
+    /// anonymizes a single line. lines that can't mention a player are returned as they are
+    pub fn line(&mut self, line: &str) -> String {
+        let kind = match line_type(line) {
+            Some(kind) => kind,
+            None => return line.to_string(),
+        };
+        // everything up to and including the line type, so the timestamp keeps its padding
+        let rest = match line.find(kind) {
+            Some(start) => &line[start + kind.len()..],
+            None => return line.to_string(),
+        };
+        let rest = rest.strip_prefix(':').unwrap_or(rest);
+        let payload = rest.strip_prefix(' ').unwrap_or(rest);
+        let prefix = &line[..line.len() - payload.len()];
+
+        let payload = match kind {
+            "InitGame" => scrub_hostname(payload),
+            "ClientUserinfoChanged" => self.userinfo(payload),
+            "Kill" => self.kill(payload),
+            "score" => self.score(payload),
+            "say" | "sayteam" => self.say(payload),
+            "tell" => self.tell(payload),
+            _ => return line.to_string(),
+        };
+        format!("{}{}", prefix, payload)
+    }
+
+    /// `<id> n\<name>\t\<team>...`. the parser takes the first value as the name, so that's the
+    /// one replaced
+    fn userinfo(&mut self, payload: &str) -> String {
+        let (id, info) = match payload.split_once(' ') {
+            Some(parts) => parts,
+            None => return payload.to_string(),
+        };
+        let mut fields: Vec<String> = info.split('\\').map(String::from).collect();
+        if fields.len() < 3 {
+            return payload.to_string();
+        }
+        if let Ok(id) = id.parse::<u32>() {
+            self.clients.insert(id, fields[1].clone());
+        }
+        fields[1] = self.pseudonym(&fields[1]);
+        format!("{} {}", id, fields.join("\\"))
+    }
+
+    /// `<killer> <victim> <means>: <killer name> killed <victim name> by <MOD>`. the names are
+    /// looked up by id, since they may contain ` killed ` themselves
+    fn kill(&mut self, payload: &str) -> String {
+        let (ids, text) = match payload.split_once(": ") {
+            Some(parts) => parts,
+            None => return payload.to_string(),
+        };
+        let (names, means_of_death) = match text.rsplit_once(" by ") {
+            Some(parts) => parts,
+            None => return payload.to_string(),
+        };
+        let mut parts = ids.split(' ').map(|id| id.parse::<u32>().ok());
+        let (killer, victim) = (parts.next().flatten(), parts.next().flatten());
+
+        let known = |id: Option<u32>, anonymizer: &Anonymizer| {
+            id.and_then(|id| anonymizer.clients.get(&id).cloned())
+        };
+        let (killer_name, victim_name) = match (known(killer, self), known(victim, self)) {
+            (Some(k), Some(v)) if names == format!("{} killed {}", k, v) => (k, v),
+            (None, Some(v)) if names == format!("<world> killed {}", v) => ("<world>".into(), v),
+            _ => match names.split_once(" killed ") {
+                Some((k, v)) => (k.to_string(), v.to_string()),
+                None => return payload.to_string(),
+            },
+        };
+        format!(
+            "{}: {} killed {} by {}",
+            ids,
+            self.pseudonym(&killer_name),
+            self.pseudonym(&victim_name),
+            means_of_death
+        )
+    }
+
+    /// `<frags>  ping: <ping>  client: <id> <name>`
+    fn score(&mut self, payload: &str) -> String {
+        let (stats, client) = match payload.split_once("client: ") {
+            Some(parts) => parts,
+            None => return payload.to_string(),
+        };
+        match client.split_once(' ') {
+            Some((id, name)) => format!("{}client: {} {}", stats, id, self.pseudonym(name)),
+            None => payload.to_string(),
+        }
+    }
+
+    /// `<name>: <message>`
+    fn say(&mut self, payload: &str) -> String {
+        match self.split_name(payload, ": ") {
+            Some(name) => format!("{}: {}", self.pseudonym(&name), SCRUBBED_MESSAGE),
+            None => SCRUBBED_MESSAGE.to_string(),
+        }
+    }
+
+    /// `<name> to <name>: <message>`
+    fn tell(&mut self, payload: &str) -> String {
+        let from = match self.split_name(payload, " to ") {
+            Some(from) => from,
+            None => return SCRUBBED_MESSAGE.to_string(),
+        };
+        let to = match self.split_name(&payload[from.len() + 4..], ": ") {
+            Some(to) => to,
+            None => return SCRUBBED_MESSAGE.to_string(),
+        };
+        format!(
+            "{} to {}: {}",
+            self.pseudonym(&from),
+            self.pseudonym(&to),
+            SCRUBBED_MESSAGE
+        )
+    }
+
+    /// the name at the start of the text, up to the separator. names seen before are preferred,
+    /// the longest first, since they may contain the separator themselves
+    fn split_name(&self, text: &str, separator: &str) -> Option<String> {
+        let known = self
+            .clients
+            .values()
+            .chain(self.pseudonyms.keys())
+            .filter(|name| {
+                text.strip_prefix(name.as_str())
+                    .is_some_and(|rest| rest.starts_with(separator))
+            })
+            .max_by_key(|name| name.len());
+        match known {
+            Some(name) => Some(name.clone()),
+            None => text.split_once(separator).map(|(name, _)| name.to_string()),
+        }
+    }
+}
+
+/// replaces the `sv_hostname` value of an info string
+fn scrub_hostname(info: &str) -> String {
+    let mut fields: Vec<&str> = info.split('\\').collect();
+    // info strings start with a backslash, so keys are on the odd positions
+    let offset = if info.starts_with('\\') { 1 } else { 0 };
+    for i in (offset..fields.len().saturating_sub(1)).step_by(2) {
+        if fields[i] == "sv_hostname" {
+            fields[i + 1] = SCRUBBED_HOSTNAME;
+        }
+    }
+    fields.join("\\")
+}
+
+/// anonymizes a whole log
+pub fn anonymize(lines: &[String]) -> Vec<String> {
+    let mut anonymizer = Anonymizer::new();
+    lines.iter().map(|line| anonymizer.line(line)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::parser::scoring::ScoringRules;
+
+    #[test]
+    fn test_anonymize_lines() {
+        let mut anonymizer = Anonymizer::new();
+        let lines = [
+            "  0:00 InitGame: \\sv_floodProtect\\1\\sv_hostname\\Code Miner Server\\mapname\\q3dm17",
+            " 20:34 ClientUserinfoChanged: 2 n\\Dono da Bola\\t\\0\\model\\sarge",
+            " 20:34 ClientUserinfoChanged: 3 n\\Zeh killed\\t\\0\\model\\sarge",
+            " 20:40 Kill: 3 2 7: Zeh killed killed Dono da Bola by MOD_ROCKET_SPLASH",
+            " 20:41 Item: 2 weapon_rocketlauncher",
+            " 20:42 say: Dono da Bola: my address is: secret",
+            " 20:43 sayteam: Zeh killed: meet me",
+            " 20:44 tell: Zeh killed to Dono da Bola: psst",
+            " 20:50 Exit: Fraglimit hit.",
+            " 20:50 score: 20  ping: 4  client: 3 Zeh killed",
+            " 20:50 score: -1  ping: 0  client: 2 Dono da Bola",
+        ];
+        let anonymized: Vec<String> = lines.iter().map(|line| anonymizer.line(line)).collect();
+        assert_eq!(
+            anonymized,
+            vec![
+                "  0:00 InitGame: \\sv_floodProtect\\1\\sv_hostname\\Anonymous Server\\mapname\\q3dm17",
+                " 20:34 ClientUserinfoChanged: 2 n\\Player 1\\t\\0\\model\\sarge",
+                " 20:34 ClientUserinfoChanged: 3 n\\Player 2\\t\\0\\model\\sarge",
+                " 20:40 Kill: 3 2 7: Player 2 killed Player 1 by MOD_ROCKET_SPLASH",
+                " 20:41 Item: 2 weapon_rocketlauncher",
+                " 20:42 say: Player 1: <removed>",
+                " 20:43 sayteam: Player 2: <removed>",
+                " 20:44 tell: Player 2 to Player 1: <removed>",
+                " 20:50 Exit: Fraglimit hit.",
+                " 20:50 score: 20  ping: 4  client: 3 Player 2",
+                " 20:50 score: -1  ping: 0  client: 2 Player 1",
+            ]
+        );
+        assert_eq!(anonymizer.pseudonyms()["Zeh killed"], "Player 2");
+    }
+
+    #[test]
+    fn test_anonymized_log_keeps_statistics() {
+        let content = std::fs::read_to_string("input").unwrap();
+        let lines: Vec<String> = content.lines().map(String::from).collect();
+
+        let mut anonymizer = Anonymizer::new();
+        let anonymized: Vec<String> = lines.iter().map(|line| anonymizer.line(line)).collect();
+        assert_eq!(anonymized.len(), lines.len());
+        assert!(!anonymized.iter().any(|line| line.contains("Isgalamido")));
+        assert!(!anonymized.iter().any(|line| line.contains("Code Miner")));
+
+        let original = parse(lines, &ScoringRules::default()).unwrap();
+        let games = parse(anonymized, &ScoringRules::default()).unwrap();
+        assert_eq!(games.len(), original.len());
+
+        let names = anonymizer.pseudonyms();
+        for (game, original) in games.iter().zip(&original) {
+            assert_eq!(game.total_kills, original.total_kills);
+            assert_eq!(game.means_of_death, original.means_of_death);
+            let players: Vec<&String> = original.player_list.iter().map(|n| &names[n]).collect();
+            assert_eq!(game.player_list.iter().collect::<Vec<_>>(), players);
+            for (name, score) in &original.kill_score {
+                assert_eq!(game.kill_score[&names[name]], *score);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pseudonyms_skip_player_names() {
+        let mut anonymizer = Anonymizer::new();
+        let lines = [
+            "  0:00 InitGame: \\mapname\\q3dm17",
+            "  0:01 ClientConnect: 2",
+            "  0:01 ClientUserinfoChanged: 2 n\\Isgalamido\\t\\0",
+            "  0:01 ClientBegin: 2",
+            "  0:02 ClientConnect: 3",
+            "  0:02 ClientUserinfoChanged: 3 n\\Player 3\\t\\0",
+            "  0:02 ClientBegin: 3",
+            "  0:03 ClientConnect: 4",
+            "  0:03 ClientUserinfoChanged: 4 n\\Player 1\\t\\0",
+            "  0:03 ClientBegin: 4",
+            "  0:04 ClientConnect: 5",
+            "  0:04 ClientUserinfoChanged: 5 n\\Zeh\\t\\0",
+            "  0:04 ClientBegin: 5",
+            "  0:05 Kill: 5 3 10: Zeh killed Player 3 by MOD_RAILGUN",
+            "  0:06 ShutdownGame:",
+        ];
+        let anonymized: Vec<String> = lines.iter().map(|line| anonymizer.line(line)).collect();
+
+        let names = anonymizer.pseudonyms();
+        assert_eq!(names["Isgalamido"], "Player 1");
+        assert_eq!(names["Player 3"], "Player 2");
+        // Player 3 is taken by a player, Player 1 is given to Isgalamido
+        assert_eq!(names["Player 1"], "Player 4");
+        assert_eq!(names["Zeh"], "Player 5");
+
+        let games = parse(anonymized, &ScoringRules::default()).unwrap();
+        assert_eq!(
+            games[0].player_list,
+            vec!["Player 1", "Player 2", "Player 4", "Player 5"]
+        );
+        assert_eq!(games[0].kill_score["Player 5"], 1);
+    }
+}
@@ -0,0 +1,514 @@
+//! generates synthetic match logs for tests and benchmarks
+//!
+//! the matches are simulated second by second from a seed, so the same [MatchConfig] always
+//! writes the same log. names are made up, so the logs can be shared freely
+
+use crate::parser::actions::Action;
+use crate::parser::game::WORLD;
+use crate::parser::writer::{timestamp, LogWriter, SEPARATOR};
+
+const NAMES: &[&str] = &[
+    "Sarge",
+    "Grunt",
+    "Visor",
+    "Major",
+    "Doom",
+    "Klesk",
+    "Anarki",
+    "Xaero",
+    "Hunter",
+    "Bones",
+    "Crash",
+    "Daemia",
+    "Mynx",
+    "Orbb",
+    "Phobos",
+    "Ranger",
+    "Razor",
+    "Slash",
+    "Sorlag",
+    "Tank Jr",
+    "Uriel",
+    "Wrack",
+    "Bitterman",
+    "Keel",
+    "Lucy",
+    "Biker",
+    "Cadavre",
+    "Hossman",
+    "Angel",
+    "Gorre",
+];
+
+const MAPS: &[&str] = &["q3dm17", "q3dm6", "q3tourney2", "q3dm13", "q3dm7", "q3dm1"];
+
+const ITEMS: &[&str] = &[
+    "weapon_rocketlauncher",
+    "weapon_railgun",
+    "weapon_shotgun",
+    "weapon_plasmagun",
+    "weapon_lightning",
+    "weapon_grenadelauncher",
+    "item_armor_shard",
+    "item_armor_combat",
+    "item_armor_body",
+    "item_health",
+    "item_health_large",
+    "item_health_mega",
+    "ammo_rockets",
+    "ammo_slugs",
+    "ammo_shells",
+    "item_quad",
+];
+
+/// means of death of kills between players: shotgun up to lightning
+const WEAPONS: &[u32] = &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+/// means of death of `<world>` kills: water, slime, lava, crush, falling and trigger hurt
+const HAZARDS: &[u32] = &[14, 15, 16, 17, 19, 22];
+/// the splash damage kills players can deal to themselves
+const SPLASHES: &[u32] = &[5, 7, 9];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    /// free for all
+    Ffa,
+    /// team deathmatch, players split between red and blue
+    Tdm,
+}
+
+/// how the generated matches are played. rates are averages, the actual events are random
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchConfig {
+    pub seed: u64,
+    pub games: usize,
+    pub mode: GameMode,
+    pub players: usize,
+    /// time limit of each game, in seconds
+    pub duration: u32,
+    /// frags that end the game early. 0 for no limit
+    pub fraglimit: i32,
+    /// kills on the whole game
+    pub kills_per_minute: f64,
+    /// share of the deaths caused by `<world>`
+    pub world_deaths: f64,
+    /// share of the deaths where players kill themselves
+    pub suicides: f64,
+    /// item pickups on the whole game
+    pub items_per_minute: f64,
+    /// name changes of each player
+    pub renames_per_minute: f64,
+    /// disconnects of each player, who comes back a few seconds later
+    pub reconnects_per_minute: f64,
+    /// team changes of each player, only on team deathmatch
+    pub team_changes_per_minute: f64,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        MatchConfig {
+            seed: 0,
+            games: 1,
+            mode: GameMode::Ffa,
+            players: 8,
+            duration: 15 * 60,
+            fraglimit: 20,
+            kills_per_minute: 20.0,
+            world_deaths: 0.1,
+            suicides: 0.05,
+            items_per_minute: 60.0,
+            renames_per_minute: 0.02,
+            reconnects_per_minute: 0.05,
+            team_changes_per_minute: 0.02,
+        }
+    }
+}
+
+/// splitmix64, small and with an output that won't change between versions of a dependency
+#[derive(Clone, Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Client {
+    id: u32,
+    name: String,
+    team: u32,
+    score: i32,
+    /// time the client comes back after disconnecting, if it's away
+    away_until: Option<u32>,
+}
+
+impl Client {
+    fn userinfo(&self) -> String {
+        format!(
+            "n\\{}\\t\\{}\\model\\sarge\\hmodel\\sarge\\g_redteam\\\\g_blueteam\\\\c1\\4\\c2\\5\\hc\\100\\w\\0\\l\\0\\tt\\0\\tl\\0",
+            self.name, self.team
+        )
+    }
+}
+
+/// plays the games described by a [MatchConfig] one at a time
+///
+/// # Example
+/// ```
+/// use q3a_log_parser::generate::{Generator, MatchConfig};
+/// use q3a_log_parser::{parse, ScoringRules};
+///
+/// let config = MatchConfig {
+///     seed: 7,
+///     players: 4,
+///     ..MatchConfig::default()
+/// };
+/// let lines = Generator::new(config).next_game();
+/// let games = parse(lines, &ScoringRules::default()).unwrap();
+/// assert_eq!(games.len(), 1);
+/// assert_eq!(games[0].player_list.len(), 4);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Generator {
+    config: MatchConfig,
+    rng: Rng,
+    played: usize,
+}
+
+impl Generator {
+    pub fn new(config: MatchConfig) -> Generator {
+        Generator {
+            rng: Rng(config.seed),
+            config,
+            played: 0,
+        }
+    }
+
+    /// the lines of the next game, from its opening separator to the closing one
+    pub fn next_game(&mut self) -> Vec<String> {
+        let mut game = GameSim {
+            config: &self.config,
+            rng: &mut self.rng,
+            writer: LogWriter::new(),
+            lines: Vec::new(),
+            clients: Vec::new(),
+            names_used: 0,
+            time: 0,
+        };
+        game.play(self.played);
+        self.played += 1;
+        game.lines
+    }
+}
+
+impl Iterator for Generator {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.played < self.config.games {
+            Some(self.next_game())
+        } else {
+            None
+        }
+    }
+}
+
+/// generates every game of the config into a single log
+pub fn generate(config: MatchConfig) -> Vec<String> {
+    Generator::new(config).flatten().collect()
+}
+
+struct GameSim<'a> {
+    config: &'a MatchConfig,
+    rng: &'a mut Rng,
+    writer: LogWriter,
+    lines: Vec<String>,
+    clients: Vec<Client>,
+    names_used: usize,
+    time: u32,
+}
+
+impl GameSim<'_> {
+    fn log(&mut self, action: Action) {
+        let line = self.writer.line(self.time, &action);
+        self.lines.push(line);
+    }
+
+    fn raw(&mut self, text: &str) {
+        self.lines
+            .push(format!("{} {}", timestamp(self.time), text));
+    }
+
+    fn new_name(&mut self) -> String {
+        let name = NAMES[self.names_used % NAMES.len()];
+        let round = self.names_used / NAMES.len();
+        self.names_used += 1;
+        match round {
+            0 => name.to_string(),
+            n => format!("{} {}", name, n + 1),
+        }
+    }
+
+    fn play(&mut self, index: usize) {
+        let config = self.config;
+        let gametype = match config.mode {
+            GameMode::Ffa => 0,
+            GameMode::Tdm => 3,
+        };
+        self.raw(SEPARATOR);
+        self.log(Action::InitGame(format!(
+            "\\sv_floodProtect\\1\\sv_maxPing\\0\\sv_minPing\\0\\sv_maxRate\\10000\\sv_minRate\\0\\sv_hostname\\Synthetic Server\\g_gametype\\{}\\sv_privateClients\\2\\sv_maxclients\\{}\\fraglimit\\{}\\timelimit\\{}\\capturelimit\\8\\version\\ioq3 1.36 linux-x86_64 Apr 12 2009\\protocol\\68\\mapname\\{}\\gamename\\baseq3\\g_needpass\\0",
+            gametype,
+            config.players.max(16),
+            config.fraglimit,
+            config.duration / 60,
+            MAPS[index % MAPS.len()]
+        )));
+
+        for i in 0..config.players {
+            let team = match config.mode {
+                GameMode::Ffa => 0,
+                GameMode::Tdm => 1 + i as u32 % 2,
+            };
+            let name = self.new_name();
+            self.clients.push(Client {
+                id: i as u32 + 2,
+                name,
+                team,
+                score: 0,
+                away_until: None,
+            });
+            self.connect(i);
+        }
+
+        let reason = loop {
+            self.time += 1;
+            if self.time >= config.duration {
+                break "Timelimit hit.";
+            }
+            self.second();
+            if config.fraglimit > 0 && self.clients.iter().any(|c| c.score >= config.fraglimit) {
+                break "Fraglimit hit.";
+            }
+        };
+
+        self.raw(&format!("Exit: {}", reason));
+        if config.mode == GameMode::Tdm {
+            let red: i32 = self.team_score(1);
+            let blue: i32 = self.team_score(2);
+            self.raw(&format!("red:{}  blue:{}", red, blue));
+        }
+        let mut present: Vec<Client> = self
+            .clients
+            .iter()
+            .filter(|c| c.away_until.is_none())
+            .cloned()
+            .collect();
+        present.sort_by_key(|c| std::cmp::Reverse(c.score));
+        for client in present {
+            let ping = self.rng.below(80);
+            self.raw(&format!(
+                "score: {}  ping: {}  client: {} {}",
+                client.score, ping, client.id, client.name
+            ));
+        }
+        self.log(Action::ShutdownGame);
+        self.raw(SEPARATOR);
+    }
+
+    fn team_score(&self, team: u32) -> i32 {
+        self.clients
+            .iter()
+            .filter(|c| c.team == team)
+            .map(|c| c.score)
+            .sum()
+    }
+
+    fn connect(&mut self, i: usize) {
+        let client = &self.clients[i];
+        let (id, userinfo) = (client.id, client.userinfo());
+        self.log(Action::ClientConnect(id));
+        self.log(Action::ClientUserinfoChanged(id, userinfo));
+        self.log(Action::ClientBegin(id));
+    }
+
+    /// everything that happens on one second of the game
+    fn second(&mut self) {
+        let config = self.config;
+        let per_second = |rate: f64| rate / 60.0;
+
+        for i in 0..self.clients.len() {
+            match self.clients[i].away_until {
+                Some(back) if back <= self.time => {
+                    self.clients[i].away_until = None;
+                    self.connect(i);
+                    continue;
+                }
+                Some(_) => continue,
+                None => {}
+            }
+
+            let id = self.clients[i].id;
+            if self.rng.chance(per_second(config.reconnects_per_minute)) {
+                self.clients[i].away_until = Some(self.time + 5 + self.rng.below(25) as u32);
+                self.log(Action::ClientDisconnect(id));
+            } else if self.rng.chance(per_second(config.renames_per_minute)) {
+                self.clients[i].name = self.new_name();
+                let userinfo = self.clients[i].userinfo();
+                self.log(Action::ClientUserinfoChanged(id, userinfo));
+            } else if config.mode == GameMode::Tdm
+                && self.rng.chance(per_second(config.team_changes_per_minute))
+            {
+                self.clients[i].team = 3 - self.clients[i].team;
+                let userinfo = self.clients[i].userinfo();
+                self.log(Action::ClientUserinfoChanged(id, userinfo));
+                self.log(Action::ClientBegin(id));
+            }
+        }
+
+        let present: Vec<usize> = (0..self.clients.len())
+            .filter(|&i| self.clients[i].away_until.is_none())
+            .collect();
+        if present.is_empty() {
+            return;
+        }
+
+        if self.rng.chance(per_second(config.items_per_minute)) {
+            let id = self.clients[*self.rng.pick(&present)].id;
+            let item = self.rng.pick(ITEMS).to_string();
+            self.log(Action::Item(id, item));
+        }
+
+        if self.rng.chance(per_second(config.kills_per_minute)) {
+            self.kill(&present);
+        }
+    }
+
+    fn kill(&mut self, present: &[usize]) {
+        let config = self.config;
+        let victim = *self.rng.pick(present);
+        let roll = (self.rng.next() >> 11) as f64 / (1u64 << 53) as f64;
+
+        if roll < config.world_deaths {
+            let means_of_death = *self.rng.pick(HAZARDS);
+            self.clients[victim].score -= 1;
+            self.log(Action::Kill(WORLD, self.clients[victim].id, means_of_death));
+            return;
+        }
+        if roll < config.world_deaths + config.suicides || present.len() < 2 {
+            let means_of_death = *self.rng.pick(SPLASHES);
+            let id = self.clients[victim].id;
+            self.clients[victim].score -= 1;
+            self.log(Action::Kill(id, id, means_of_death));
+            return;
+        }
+
+        // players mostly kill the other team on team deathmatch
+        let team = self.clients[victim].team;
+        let enemies: Vec<usize> = present
+            .iter()
+            .copied()
+            .filter(|&i| i != victim && (team == 0 || self.clients[i].team != team))
+            .collect();
+        let killer = if enemies.is_empty() {
+            *self.rng.pick(
+                &present
+                    .iter()
+                    .copied()
+                    .filter(|&i| i != victim)
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            *self.rng.pick(&enemies)
+        };
+
+        let means_of_death = *self.rng.pick(WEAPONS);
+        self.clients[killer].score += 1;
+        let (killer, victim) = (self.clients[killer].id, self.clients[victim].id);
+        self.log(Action::Kill(killer, victim, means_of_death));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::parser::player::Team;
+    use crate::parser::scoring::ScoringRules;
+    use crate::parser::validate::validate;
+
+    #[test]
+    fn test_deterministic() {
+        let config = MatchConfig {
+            seed: 42,
+            games: 3,
+            ..MatchConfig::default()
+        };
+        assert_eq!(generate(config.clone()), generate(config.clone()));
+
+        let other = MatchConfig {
+            seed: 43,
+            ..config.clone()
+        };
+        assert_ne!(generate(config), generate(other));
+    }
+
+    #[test]
+    fn test_generated_log_parses() {
+        let config = MatchConfig {
+            seed: 1,
+            games: 4,
+            players: 6,
+            renames_per_minute: 0.5,
+            reconnects_per_minute: 0.5,
+            ..MatchConfig::default()
+        };
+        let lines = generate(config);
+        assert!(validate(&lines).is_empty());
+
+        let games = parse(lines.clone(), &ScoringRules::default()).unwrap();
+        assert_eq!(games.len(), 4);
+        assert_eq!(games[1].map(), Some("q3dm6"));
+        for game in &games {
+            assert!(game.total_kills > 0);
+            assert!(game.players.len() >= 6);
+        }
+        assert!(lines.iter().any(|line| line.contains("ClientDisconnect")));
+    }
+
+    #[test]
+    fn test_team_deathmatch() {
+        let config = MatchConfig {
+            seed: 3,
+            mode: GameMode::Tdm,
+            players: 6,
+            team_changes_per_minute: 0.5,
+            ..MatchConfig::default()
+        };
+        let lines = generate(config);
+        assert!(validate(&lines).is_empty());
+        assert!(lines.iter().any(|line| line.contains(" red:")));
+
+        let games = parse(lines, &ScoringRules::default()).unwrap();
+        let teams: Vec<Team> = games[0].players.iter().map(|p| p.team).collect();
+        assert!(teams.contains(&Team::Red));
+        assert!(teams.contains(&Team::Blue));
+    }
+}
@@ -0,0 +1,449 @@
+//! reading logs from files, directories, glob patterns or stdin
+//!
+//! compressed logs (gzip, zstd or xz) are decompressed on the fly, and lines written in a legacy
+//! encoding are decoded instead of dropped
+
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// order multiple input files are read in. rotated logs are named after the date they were
+/// started, so either order is chronological
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// sorted by file name, with numbers compared by value so `games.log.2` comes before
+    /// `games.log.10`
+    Name,
+    /// sorted by last modification time
+    Mtime,
+}
+
+/// encoding used for lines that aren't valid UTF-8
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Latin1,
+    /// Windows-1252, Latin-1 with printable characters on 0x80-0x9f
+    Cp1252,
+    /// UTF-8 with invalid bytes replaced by U+FFFD
+    Lossy,
+}
+
+/// expands file arguments into the list of files to read. directories are replaced by the
+/// files directly inside them and anything that isn't an existing path is read as a glob pattern
+pub fn resolve_files(args: &[String], order: Order) -> Result<Vec<String>, String> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for arg in args {
+        let path = Path::new(arg);
+        if path.is_dir() {
+            let entries = match std::fs::read_dir(path) {
+                Ok(entries) => entries,
+                Err(e) => return Err(format!("could not read directory {}: {}", arg, e)),
+            };
+            for entry in entries {
+                let entry =
+                    entry.map_err(|e| format!("could not read directory {}: {}", arg, e))?;
+                if entry.path().is_file() {
+                    files.push(entry.path());
+                }
+            }
+        } else if path.exists() {
+            files.push(path.to_path_buf());
+        } else {
+            let matches = match glob::glob(arg) {
+                Ok(matches) => matches,
+                Err(e) => return Err(format!("invalid pattern {}: {}", arg, e)),
+            };
+            let before = files.len();
+            for entry in matches {
+                let entry = entry.map_err(|e| format!("could not read {}: {}", arg, e))?;
+                if entry.is_file() {
+                    files.push(entry);
+                }
+            }
+            if files.len() == before {
+                return Err(format!("no files found for {}", arg));
+            }
+        }
+    }
+
+    match order {
+        Order::Name => files.sort_by(|a, b| {
+            let x = a.file_name().unwrap_or_default().to_string_lossy();
+            let y = b.file_name().unwrap_or_default().to_string_lossy();
+            natural_cmp(&x, &y).then(a.cmp(b))
+        }),
+        Order::Mtime => {
+            let mut modified = Vec::with_capacity(files.len());
+            for file in files {
+                let time = match std::fs::metadata(&file).and_then(|m| m.modified()) {
+                    Ok(time) => time,
+                    Err(e) => return Err(format!("could not read {}: {}", file.display(), e)),
+                };
+                modified.push((time, file));
+            }
+            modified.sort();
+            files = modified.into_iter().map(|(_, file)| file).collect();
+        }
+    }
+    files.dedup();
+
+    Ok(files
+        .into_iter()
+        .map(|file| file.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// compares names a run of characters at a time, reading runs of digits as numbers
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    while !a.is_empty() && !b.is_empty() {
+        let digits = a.starts_with(|c: char| c.is_ascii_digit());
+        if digits != b.starts_with(|c: char| c.is_ascii_digit()) {
+            return a.cmp(b);
+        }
+        let run = |s: &str| {
+            s.find(|c: char| c.is_ascii_digit() != digits)
+                .unwrap_or(s.len())
+        };
+        let (x, rest_a) = a.split_at(run(a));
+        let (y, rest_b) = b.split_at(run(b));
+        let order = if digits {
+            // leading zeros don't change the number, and a longer number is a bigger one
+            let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+            x.len().cmp(&y.len()).then(x.cmp(y))
+        } else {
+            x.cmp(y)
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+        (a, b) = (rest_a, rest_b);
+    }
+    a.len().cmp(&b.len())
+}
+
+/// lines read from an input
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    pub lines: Vec<String>,
+    /// lines, starting at 1, that weren't valid UTF-8 and were decoded with the fallback encoding
+    pub fallback: Vec<usize>,
+}
+
+/// reads a file, or stdin when `file` is `None`, decompressing it if needed
+pub fn read_input(file: Option<String>, encoding: Encoding) -> Result<Input, String> {
+    let reader: Box<dyn Read> = match &file {
+        None => Box::new(std::io::stdin()),
+        Some(file) => match File::open(file) {
+            Ok(f) => Box::new(f),
+            Err(e) => return Err(format!("could not open file: {}", e)),
+        },
+    };
+    let reader = decompress(BufReader::new(reader), file.as_deref())?;
+    read_lines(reader, encoding)
+}
+
+/// FNV-1a hash of the lines of a log. it is stable between builds, so it tells apart reports of
+/// different logs even when they were read from the same path or from stdin
+pub fn fingerprint(lines: &[String]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in lines.iter().flat_map(|line| line.bytes().chain([b'\n'])) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+/// detects the compression from the magic bytes at the start of the stream. the file extension is
+/// only used when the stream is too short to tell
+pub fn detect_compression(magic: &[u8], file: Option<&str>) -> Compression {
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        return Compression::Gzip;
+    }
+    if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        return Compression::Zstd;
+    }
+    if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        return Compression::Xz;
+    }
+    if magic.len() >= 6 {
+        return Compression::None;
+    }
+
+    match file.and_then(|file| Path::new(file).extension()) {
+        Some(ext) if ext == "gz" => Compression::Gzip,
+        Some(ext) if ext == "zst" => Compression::Zstd,
+        Some(ext) if ext == "xz" => Compression::Xz,
+        _ => Compression::None,
+    }
+}
+
+/// wraps the reader into a streaming decoder for its compression, if any
+pub fn decompress<R: BufRead + 'static>(
+    mut reader: R,
+    file: Option<&str>,
+) -> Result<Box<dyn BufRead>, String> {
+    let magic = match reader.fill_buf() {
+        Ok(magic) => magic,
+        Err(e) => return Err(format!("could not read input: {}", e)),
+    };
+
+    Ok(match detect_compression(magic, file) {
+        Compression::None => Box::new(reader),
+        // logrotate may append several gzip members to the same file
+        Compression::Gzip => Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))),
+        Compression::Zstd => match zstd::stream::read::Decoder::with_buffer(reader) {
+            Ok(decoder) => Box::new(BufReader::new(decoder)),
+            Err(e) => return Err(format!("could not read zstd stream: {}", e)),
+        },
+        Compression::Xz => Box::new(BufReader::new(xz2::bufread::XzDecoder::new_multi_decoder(
+            reader,
+        ))),
+    })
+}
+
+/// reads every line, stopping on read errors such as a corrupted archive. lines that aren't valid
+/// UTF-8 are decoded with the fallback encoding, so no line is ever dropped
+pub fn read_lines<R: BufRead>(mut reader: R, encoding: Encoding) -> Result<Input, String> {
+    let mut input = Input {
+        lines: Vec::new(),
+        fallback: Vec::new(),
+    };
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => return Ok(input),
+            Ok(_) => (),
+            Err(e) => return Err(format!("could not read input: {}", e)),
+        }
+
+        if buf.ends_with(b"\n") {
+            buf.pop();
+            if buf.ends_with(b"\r") {
+                buf.pop();
+            }
+        }
+        match std::str::from_utf8(&buf) {
+            Ok(line) => input.lines.push(line.to_string()),
+            Err(_) => {
+                input.lines.push(decode(&buf, encoding));
+                input.fallback.push(input.lines.len());
+            }
+        }
+    }
+}
+
+// characters Windows-1252 puts on 0x80-0x9f. the five bytes it leaves undefined map to the C1
+// control with the same value, like Latin-1 does
+const CP1252_HIGH: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+fn decode(bytes: &[u8], encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+        Encoding::Cp1252 => bytes
+            .iter()
+            .map(|&b| match b {
+                0x80..=0x9f => CP1252_HIGH[(b - 0x80) as usize],
+                _ => b as char,
+            })
+            .collect(),
+        Encoding::Lossy => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_read_input() {
+        // Test reading from a file
+        std::fs::write("test_input.txt", "Hello\nworld").unwrap();
+        let result = read_input(Some("test_input.txt".to_string()), Encoding::Cp1252);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().lines, vec!["Hello", "world"]);
+
+        // Clean up
+        std::fs::remove_file("test_input.txt").unwrap();
+    }
+
+    #[test]
+    fn test_read_compressed_input() {
+        let content = "  0:00 InitGame: \\mapname\\q3dm17\n  0:01 ShutdownGame:\n";
+        let expected: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(content.as_bytes()).unwrap();
+        let gz = gz.finish().unwrap();
+        let zst = zstd::stream::encode_all(content.as_bytes(), 0).unwrap();
+        let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(content.as_bytes()).unwrap();
+        let xz = xz.finish().unwrap();
+
+        // extensions are deliberately wrong, the magic bytes take precedence
+        for (file, bytes) in [
+            ("test_input.gz", gz.clone()),
+            ("test_input.zst", zst),
+            ("test_input.txt.xz", xz),
+            ("test_input_gz.log", gz.clone()),
+        ] {
+            std::fs::write(file, bytes).unwrap();
+            let result = read_input(Some(file.to_string()), Encoding::Cp1252);
+            std::fs::remove_file(file).unwrap();
+            assert_eq!(result.map(|input| input.lines), Ok(expected.clone()));
+        }
+
+        // a truncated archive is an error instead of a partial read
+        std::fs::write("test_input_truncated.gz", &gz[..gz.len() / 2]).unwrap();
+        let result = read_input(
+            Some("test_input_truncated.gz".to_string()),
+            Encoding::Cp1252,
+        );
+        std::fs::remove_file("test_input_truncated.gz").unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_read_non_utf8_lines() {
+        // "João" and a euro sign written by a Windows-1252 server, between two UTF-8 lines
+        let content: &[u8] =
+            b"  0:01 ClientConnect: 2\n  0:01 say: Jo\xe3o: 5\x80\r\n  0:02 say: Zeh: ol\xc3\xa1\n";
+        let read = |encoding| read_lines(content, encoding).unwrap();
+
+        let cp1252 = read(Encoding::Cp1252);
+        assert_eq!(
+            cp1252.lines,
+            vec![
+                "  0:01 ClientConnect: 2",
+                "  0:01 say: João: 5€",
+                "  0:02 say: Zeh: olá"
+            ]
+        );
+        assert_eq!(cp1252.fallback, vec![2]);
+        assert_eq!(read(Encoding::Latin1).lines[1], "  0:01 say: João: 5\u{80}");
+        assert_eq!(
+            read(Encoding::Lossy).lines[1],
+            "  0:01 say: Jo\u{fffd}o: 5\u{fffd}"
+        );
+    }
+
+    #[test]
+    fn test_detect_compression() {
+        assert_eq!(
+            detect_compression(b"  0:00 InitGame:", Some("games.log.gz")),
+            Compression::None
+        );
+        assert_eq!(
+            detect_compression(&[0x1f, 0x8b, 8, 0], None),
+            Compression::Gzip
+        );
+        assert_eq!(
+            detect_compression(b"", Some("games.log.zst")),
+            Compression::Zstd
+        );
+        assert_eq!(
+            detect_compression(b"", Some("games.log")),
+            Compression::None
+        );
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec![
+            "games.log.10",
+            "games.log",
+            "games.log.2",
+            "games.log.02",
+            "games.log.1",
+            "games-2024-01-09.log",
+            "games-2024-01-10.log",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b).then(a.cmp(b)));
+        assert_eq!(
+            names,
+            vec![
+                "games-2024-01-09.log",
+                "games-2024-01-10.log",
+                "games.log",
+                "games.log.1",
+                "games.log.02",
+                "games.log.2",
+                "games.log.10",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let log = |lines: &[&str]| -> Vec<String> { lines.iter().map(|l| l.to_string()).collect() };
+        let monday = log(&["  0:00 InitGame: ", "  0:10 ShutdownGame:"]);
+        let tuesday = log(&["  0:00 InitGame: ", "  0:11 ShutdownGame:"]);
+
+        assert_eq!(fingerprint(&monday), fingerprint(&monday.clone()));
+        assert_ne!(fingerprint(&monday), fingerprint(&tuesday));
+        // lines are hashed with their ends, so moving text between lines changes it
+        assert_ne!(
+            fingerprint(&log(&["ab", "c"])),
+            fingerprint(&log(&["a", "bc"]))
+        );
+        assert_eq!(fingerprint(&[]).len(), 16);
+    }
+
+    #[test]
+    fn test_resolve_files() {
+        let dir = "test_resolve_files";
+        let _ = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(format!("{}/nested", dir)).unwrap();
+        let now = std::time::SystemTime::now();
+        for (i, name) in ["games.log.2", "games.log.10", "games.log.1", "other.txt"]
+            .iter()
+            .enumerate()
+        {
+            let file = File::create(format!("{}/{}", dir, name)).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(60 * i as u64))
+                .unwrap();
+        }
+
+        let by_name = resolve_files(&[dir.to_string()], Order::Name);
+        let by_mtime = resolve_files(&[format!("{}/games.log.*", dir)], Order::Mtime);
+        let single = resolve_files(&[format!("{}/other.txt", dir)], Order::Name);
+        let missing = resolve_files(&[format!("{}/*.gz", dir)], Order::Name);
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(
+            by_name,
+            Ok(vec![
+                format!("{}/games.log.1", dir),
+                format!("{}/games.log.2", dir),
+                format!("{}/games.log.10", dir),
+                format!("{}/other.txt", dir),
+            ])
+        );
+        assert_eq!(
+            by_mtime,
+            Ok(vec![
+                format!("{}/games.log.1", dir),
+                format!("{}/games.log.10", dir),
+                format!("{}/games.log.2", dir),
+            ])
+        );
+        assert_eq!(single, Ok(vec![format!("{}/other.txt", dir)]));
+        assert_eq!(missing, Err(format!("no files found for {}/*.gz", dir)));
+    }
+}
@@ -0,0 +1,48 @@
+//! parses quake 3 arena server logs into per game reports
+//!
+//! the [parser] module turns log lines into [Action]s, groups them into games and replays each
+//! game into a [Game] holding its scores, means of death, streaks, awards and play time. the
+//! [report] module renders parsed games into the formats supported by the CLI.
+//!
+//! # Example
+//! ```
+//! use q3a_log_parser::{parse, rank, ScoringRules};
+//!
+//! let log = "  0:00 InitGame: \\mapname\\q3dm17
+//!   0:01 ClientConnect: 2
+//!   0:01 ClientUserinfoChanged: 2 n\\Isgalamido\\t\\0
+//!   0:01 ClientBegin: 2
+//!   0:02 ClientConnect: 3
+//!   0:02 ClientUserinfoChanged: 3 n\\Zeh\\t\\0
+//!   0:02 ClientBegin: 3
+//!   0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN
+//!   0:20 ShutdownGame:";
+//! let lines = log.lines().map(|line| line.to_string()).collect();
+//!
+//! let games = parse(lines, &ScoringRules::default()).unwrap();
+//! assert_eq!(games.len(), 1);
+//! assert_eq!(games[0].map(), Some("q3dm17"));
+//! assert_eq!(games[0].kill_score["Isgalamido"], 1);
+//! assert_eq!(rank(&games)[0].name, "Isgalamido");
+//! ```
+pub mod anonymize;
+pub mod generate;
+pub mod input;
+pub mod parser;
+pub mod report;
+
+pub use generate::{generate, GameMode, Generator, MatchConfig};
+pub use parser::actions::{Action, Event};
+pub use parser::collector::{Collectors, StatCollector};
+pub use parser::game::{Game, KillMode, KillRecord, BUILTIN_FIELDS};
+pub use parser::player::{PlayTime, Player, Players, Team};
+pub use parser::ranking::{rank, PlayerRanking};
+pub use parser::scoring::ScoringRules;
+pub use parser::validate::{validate, Anomaly, AnomalyKind};
+pub use parser::writer::{write_log, LogWriter};
+pub use parser::{line_type, parse, parse_events, parse_parallel, parse_with};
+pub use report::csv::Table;
+pub use report::json::{Report, REPORT_VERSION};
+pub use report::ndjson::EventRecord;
+pub use report::sqlite::ExportSummary;
+pub use report::stats::LogStats;
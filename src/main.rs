@@ -1,40 +1,339 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use q3a_log_parser::anonymize::Anonymizer;
+use q3a_log_parser::generate::{GameMode, Generator, MatchConfig};
+use q3a_log_parser::parser::collector::Collectors;
+use q3a_log_parser::parser::game::Game;
+use q3a_log_parser::parser::scoring::ScoringRules;
+use q3a_log_parser::{input, parser, report};
+use serde::Serialize;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
+use std::io::Write;
+use std::path::Path;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    /// name of the file to read. if none = read from stdin
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// without a subcommand the log is parsed into the JSON report, same as `parse`
+    #[command(flatten)]
+    parse: ParseArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// parse the log into the JSON map of games
+    Parse(ParseArgs),
+    /// write the games in one of the report formats
+    Report(ReportArgs),
+    /// rank the players across every game
+    Rank(RankArgs),
+    /// check the log for anomalies, exiting with an error if any is found
+    Validate(ValidateArgs),
+    /// write each game of the log into its own file
+    Split(SplitArgs),
+    /// summarize the log: line counts by type, games and players
+    Stats(StatsArgs),
+    /// write a synthetic log of simulated matches, the same for the same seed
+    Generate(GenerateArgs),
+    /// rewrite the log with pseudonyms instead of player names and without chat or hostname
+    Anonymize(AnonymizeArgs),
+}
+
+#[derive(Args, Debug)]
+struct InputArgs {
+    /// files, directories or glob patterns to read. games from every file are concatenated.
+    /// if none = read from stdin
+    #[arg(long, num_args = 1..)]
+    file: Vec<String>,
+
+    /// order multiple input files are read in
+    #[arg(long, value_enum, default_value_t = Order::Name)]
+    order: Order,
+
+    /// encoding used for lines that aren't valid UTF-8, common on player names from old servers
+    #[arg(long, value_enum, default_value_t = Encoding::Cp1252)]
+    encoding: Encoding,
+}
+
+#[derive(Args, Debug)]
+struct ScoringArgs {
+    /// scoring rules to use: a preset (challenge, quake3, no-world-penalty) or a TOML/JSON rule file
+    #[arg(long, default_value = "challenge")]
+    scoring: String,
+
+    /// seconds between kills for them to count as a multi kill. overrides the scoring rules
     #[arg(long)]
-    file: Option<String>,
+    multi_kill_window: Option<u32>,
+}
+
+#[derive(Args, Debug)]
+struct JobsArgs {
+    /// threads to parse the games on. 0 = one per available core. the output is identical to
+    /// parsing on a single thread
+    #[arg(long, default_value_t = 1)]
+    jobs: usize,
+}
+
+impl JobsArgs {
+    fn threads(&self) -> usize {
+        match self.jobs {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            jobs => jobs,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct ParseArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    scoring: ScoringArgs,
+
+    #[command(flatten)]
+    jobs: JobsArgs,
 
     /// where to output the results. if none = write to stdout
     #[arg(long)]
     output: Option<String>,
+
+    /// output every parsed event as a line of JSON instead of the per game report
+    #[arg(long)]
+    events: bool,
 }
 
-mod parser;
+#[derive(Args, Debug)]
+struct ReportArgs {
+    #[command(flatten)]
+    input: InputArgs,
 
-fn main() {
-    let args = Args::parse();
+    #[command(flatten)]
+    scoring: ScoringArgs,
+
+    #[command(flatten)]
+    jobs: JobsArgs,
+
+    /// where to output the report. if none = write to stdout
+    #[arg(long)]
+    output: Option<String>,
+
+    /// csv writes one file per table into the --output directory, sqlite appends the games to the
+    /// --output database, ndjson writes one game per line and report writes a versioned report
+    /// that can be merged later
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+}
+
+#[derive(Args, Debug)]
+struct RankArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    scoring: ScoringArgs,
+
+    #[command(flatten)]
+    jobs: JobsArgs,
+
+    /// where to output the ranking. if none = write to stdout
+    #[arg(long)]
+    output: Option<String>,
+
+    /// JSON reports from previous runs to merge into a single ranking. no log is read
+    #[arg(long, num_args = 1..)]
+    merge: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct ValidateArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    /// where to output the anomalies found, one JSON object per line. if none = write to stdout
+    #[arg(long)]
+    output: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct SplitArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    /// directory to write the games into, created if needed
+    #[arg(long)]
+    output: String,
+}
 
-    let lines = match read_input(args.file) {
-        Ok(lines) => lines,
-        Err(e) => {
-            eprintln!("could not read input: {}", e);
-            std::process::exit(1);
+#[derive(Args, Debug)]
+struct StatsArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    jobs: JobsArgs,
+
+    /// where to output the summary. if none = write to stdout
+    #[arg(long)]
+    output: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct AnonymizeArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    /// where to write the anonymized log. if none = write to stdout
+    #[arg(long)]
+    output: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct GenerateArgs {
+    /// where to write the log. if none = write to stdout
+    #[arg(long)]
+    output: Option<String>,
+
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    #[arg(long, default_value_t = 1)]
+    games: usize,
+
+    #[arg(long, value_enum, default_value_t = Mode::Ffa)]
+    mode: Mode,
+
+    #[arg(long, default_value_t = 8)]
+    players: usize,
+
+    /// time limit of each game, in seconds
+    #[arg(long, default_value_t = 900)]
+    duration: u32,
+
+    /// frags that end a game early. 0 for no limit
+    #[arg(long, default_value_t = 20)]
+    fraglimit: i32,
+
+    #[arg(long, default_value_t = 20.0)]
+    kills_per_minute: f64,
+
+    /// share of the deaths caused by <world>
+    #[arg(long, default_value_t = 0.1)]
+    world_deaths: f64,
+
+    /// share of the deaths where players kill themselves
+    #[arg(long, default_value_t = 0.05)]
+    suicides: f64,
+
+    #[arg(long, default_value_t = 60.0)]
+    items_per_minute: f64,
+
+    /// name changes of each player
+    #[arg(long, default_value_t = 0.02)]
+    renames_per_minute: f64,
+
+    /// disconnects of each player, who comes back a few seconds later
+    #[arg(long, default_value_t = 0.05)]
+    reconnects_per_minute: f64,
+
+    /// team changes of each player, only on team deathmatch
+    #[arg(long, default_value_t = 0.02)]
+    team_changes_per_minute: f64,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    /// free for all
+    Ffa,
+    /// team deathmatch
+    Tdm,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq, Eq)]
+enum Format {
+    Json,
+    Csv,
+    Html,
+    Markdown,
+    Ndjson,
+    Report,
+    Sqlite,
+}
+
+/// rotated logs are named after the date they were started, so either order is chronological
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Order {
+    /// sorted by file name, with numbers compared by value (games.log.2 before games.log.10)
+    Name,
+    /// sorted by last modification time
+    Mtime,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    Latin1,
+    /// Windows-1252, Latin-1 with printable characters on 0x80-0x9f
+    Cp1252,
+    /// UTF-8 with invalid bytes replaced by U+FFFD
+    Lossy,
+}
+
+impl From<Order> for input::Order {
+    fn from(order: Order) -> Self {
+        match order {
+            Order::Name => input::Order::Name,
+            Order::Mtime => input::Order::Mtime,
         }
-    };
+    }
+}
 
-    let parsed = match parser::parse(lines) {
-        Ok(parsed) => parsed,
-        Err(e) => {
-            eprintln!("could not parse input: {}", e);
-            std::process::exit(1);
+impl From<Encoding> for input::Encoding {
+    fn from(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::Latin1 => input::Encoding::Latin1,
+            Encoding::Cp1252 => input::Encoding::Cp1252,
+            Encoding::Lossy => input::Encoding::Lossy,
         }
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        None => run_parse(cli.parse),
+        Some(Command::Parse(args)) => run_parse(args),
+        Some(Command::Report(args)) => run_report(args),
+        Some(Command::Rank(args)) => run_rank(args),
+        Some(Command::Validate(args)) => run_validate(args),
+        Some(Command::Split(args)) => run_split(args),
+        Some(Command::Stats(args)) => run_stats(args),
+        Some(Command::Generate(args)) => run_generate(args),
+        Some(Command::Anonymize(args)) => run_anonymize(args),
     };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run_parse(args: ParseArgs) -> Result<(), String> {
+    let inputs = read_inputs(&args.input)?;
+    let scoring = load_scoring(&args.scoring)?;
 
+    if args.events {
+        let events: Vec<_> = inputs
+            .into_iter()
+            .flat_map(|input| parser::parse_events(input.lines))
+            .collect();
+        let stream = report::ndjson::events(&events, &scoring)
+            .map_err(|e| format!("could not parse input: {}", e))?;
+        return write_output(args.output, stream);
+    }
+
+    let parsed = parse_inputs(inputs, &scoring, &args.jobs)?;
     // this last step is just for presentation purposes. the output is a map of games
     // as presented on [the challenge](challenge.md)
     let mut games_map = std::collections::HashMap::new();
@@ -42,29 +341,294 @@ fn main() {
         games_map.insert(format!("game_{}", i), game);
     });
 
-    write_output(args.output, serde_json::to_string(&games_map).unwrap()).unwrap();
+    write_output(args.output, serde_json::to_string(&games_map).unwrap())
 }
 
-fn read_input(file: Option<String>) -> Result<Vec<String>, String> {
-    match file {
-        None => {
-            let reader = BufReader::new(std::io::stdin());
-            let lines = reader
-                .lines()
-                .filter(|e| e.is_ok())
-                .map(|e| e.unwrap())
+fn run_report(args: ReportArgs) -> Result<(), String> {
+    let inputs = read_inputs(&args.input)?;
+    let scoring = load_scoring(&args.scoring)?;
+    let parsed = parse_inputs(inputs, &scoring, &args.jobs)?;
+
+    match args.format {
+        Format::Json => {
+            let games_map: std::collections::HashMap<String, &Game> = parsed
+                .iter()
+                .enumerate()
+                .map(|(i, game)| (format!("game_{}", i), game))
                 .collect();
-            Ok(lines)
+            write_output(args.output, serde_json::to_string(&games_map).unwrap())
         }
-        Some(file) => {
-            let f = match File::open(file) {
-                Ok(f) => f,
-                Err(e) => return Err(format!("could not open file: {}", e)),
+        Format::Csv => match args.output {
+            Some(dir) => write_tables(dir, report::csv::tables(&parsed)),
+            None => write_output(None, report::csv::render(&parsed)),
+        },
+        Format::Html => write_output(args.output, report::html::render(&parsed)),
+        Format::Markdown => write_output(args.output, report::markdown::render(&parsed)),
+        Format::Ndjson => write_output(args.output, report::ndjson::games(&parsed)),
+        Format::Report => {
+            let report = report::json::Report::new(parsed);
+            write_output(args.output, serde_json::to_string(&report).unwrap())
+        }
+        Format::Sqlite => {
+            let path = match args.output {
+                Some(path) => path,
+                None => return Err("the sqlite format requires an --output database file".into()),
+            };
+            let summary = report::sqlite::export(&path, &parsed)
+                .map_err(|e| format!("could not export games: {}", e))?;
+            eprintln!(
+                "imported {} games, skipped {} already on the database",
+                summary.imported, summary.skipped
+            );
+            Ok(())
+        }
+    }
+}
+
+fn run_rank(args: RankArgs) -> Result<(), String> {
+    let ranking = if args.merge.is_empty() {
+        let inputs = read_inputs(&args.input)?;
+        let scoring = load_scoring(&args.scoring)?;
+        parser::ranking::rank(&parse_inputs(inputs, &scoring, &args.jobs)?)
+    } else {
+        let mut reports = Vec::with_capacity(args.merge.len());
+        for path in &args.merge {
+            let report = report::json::Report::load(path)
+                .map_err(|e| format!("could not load report: {}", e))?;
+            reports.push(report);
+        }
+        report::json::merge(&reports)
+    };
+
+    write_output(args.output, serde_json::to_string(&ranking).unwrap())
+}
+
+fn run_validate(args: ValidateArgs) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct AnomalyLine<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        file: Option<&'a str>,
+        #[serde(flatten)]
+        anomaly: &'a parser::validate::Anomaly,
+    }
+
+    let inputs = read_inputs(&args.input)?;
+
+    let mut found = 0;
+    let mut lines = String::new();
+    for input in &inputs {
+        for anomaly in parser::validate::validate(&input.lines) {
+            let line = AnomalyLine {
+                file: input.file.as_deref(),
+                anomaly: &anomaly,
             };
-            let reader = BufReader::new(f).lines();
-            Ok(reader.filter(|e| e.is_ok()).map(|e| e.unwrap()).collect())
+            lines += &(serde_json::to_string(&line).unwrap() + "\n");
+            found += 1;
         }
     }
+    if found > 0 || args.output.is_some() {
+        write_output(args.output, lines)?;
+    }
+
+    match found {
+        0 => Ok(()),
+        n => Err(format!("found {} anomalies", n)),
+    }
+}
+
+fn run_split(args: SplitArgs) -> Result<(), String> {
+    let inputs = read_inputs(&args.input)?;
+    if let Err(e) = std::fs::create_dir_all(&args.output) {
+        return Err(format!("could not create directory: {}", e));
+    }
+
+    let games: Vec<_> = inputs
+        .iter()
+        .flat_map(|input| parser::split::split(&input.lines))
+        .collect();
+    let count = games.len();
+    let width = count.saturating_sub(1).to_string().len();
+    for (index, mut game) in games.into_iter().enumerate() {
+        // games from several files are numbered one after the other
+        game.index = index;
+        let path = Path::new(&args.output).join(game.file_name(width));
+        let content = game.lines.join("\n") + "\n";
+        write_output(Some(path.to_string_lossy().to_string()), content)?;
+    }
+    eprintln!("wrote {} games into {}", count, args.output);
+    Ok(())
+}
+
+fn run_stats(args: StatsArgs) -> Result<(), String> {
+    let inputs = read_inputs(&args.input)?;
+
+    let lines: Vec<String> = inputs
+        .iter()
+        .flat_map(|input| input.lines.iter().cloned())
+        .collect();
+    let games = parse_inputs(inputs, &ScoringRules::default(), &args.jobs)?;
+    let stats = report::stats::summarize(&lines, &games);
+
+    write_output(args.output, serde_json::to_string(&stats).unwrap())
+}
+
+fn run_generate(args: GenerateArgs) -> Result<(), String> {
+    let config = MatchConfig {
+        seed: args.seed,
+        games: args.games,
+        mode: match args.mode {
+            Mode::Ffa => GameMode::Ffa,
+            Mode::Tdm => GameMode::Tdm,
+        },
+        players: args.players,
+        duration: args.duration,
+        fraglimit: args.fraglimit,
+        kills_per_minute: args.kills_per_minute,
+        world_deaths: args.world_deaths,
+        suicides: args.suicides,
+        items_per_minute: args.items_per_minute,
+        renames_per_minute: args.renames_per_minute,
+        reconnects_per_minute: args.reconnects_per_minute,
+        team_changes_per_minute: args.team_changes_per_minute,
+    };
+
+    // large logs are written a game at a time instead of being built in memory
+    let out: Box<dyn Write> = match args.output {
+        Some(path) => match File::create(path) {
+            Ok(f) => Box::new(f),
+            Err(e) => return Err(format!("could not create file: {}", e)),
+        },
+        None => Box::new(std::io::stdout()),
+    };
+    let mut out = std::io::BufWriter::new(out);
+    for game in Generator::new(config) {
+        for line in game {
+            if let Err(e) = writeln!(out, "{}", line) {
+                return Err(format!("could not write log: {}", e));
+            }
+        }
+    }
+    out.flush()
+        .map_err(|e| format!("could not write log: {}", e))
+}
+
+fn run_anonymize(args: AnonymizeArgs) -> Result<(), String> {
+    let inputs = read_inputs(&args.input)?;
+
+    // one anonymizer for every file, so a player keeps the same pseudonym on all of them
+    let mut anonymizer = Anonymizer::new();
+    let lines: Vec<String> = inputs
+        .iter()
+        .flat_map(|input| input.lines.iter())
+        .map(|line| anonymizer.line(line))
+        .collect();
+    eprintln!("replaced {} player names", anonymizer.pseudonyms().len());
+
+    // every line ends with a newline, the last one included, like on the server's own logs
+    let mut content = lines.join("\n");
+    if !lines.is_empty() {
+        content.push('\n');
+    }
+    write_output(args.output, content)
+}
+
+/// the lines read from one input, along with the file they came from. `None` for stdin
+struct LogFile {
+    file: Option<String>,
+    lines: Vec<String>,
+}
+
+/// reads every input file, or stdin when no file was given
+fn read_inputs(args: &InputArgs) -> Result<Vec<LogFile>, String> {
+    let files = input::resolve_files(&args.file, args.order.into())
+        .map_err(|e| format!("could not read input: {}", e))?;
+    // stdin is read once, with no file to tag the games with
+    let sources: Vec<Option<String>> = if files.is_empty() {
+        vec![None]
+    } else {
+        files.into_iter().map(Some).collect()
+    };
+
+    let mut inputs = Vec::with_capacity(sources.len());
+    for file in sources {
+        let input = input::read_input(file.clone(), args.encoding.into())
+            .map_err(|e| format!("could not read input: {}", e))?;
+        if !input.fallback.is_empty() {
+            eprintln!(
+                "{}: {} lines were not valid UTF-8 and were decoded as {} (lines {})",
+                file.as_deref().unwrap_or("stdin"),
+                input.fallback.len(),
+                args.encoding.to_possible_value().unwrap().get_name(),
+                summarize_lines(&input.fallback)
+            );
+        }
+        inputs.push(LogFile {
+            file,
+            lines: input.lines,
+        });
+    }
+    Ok(inputs)
+}
+
+fn load_scoring(args: &ScoringArgs) -> Result<ScoringRules, String> {
+    let mut scoring = ScoringRules::load(&args.scoring)
+        .map_err(|e| format!("could not load scoring rules: {}", e))?;
+    if let Some(window) = args.multi_kill_window {
+        scoring.multi_kill_window = window;
+    }
+    Ok(scoring)
+}
+
+/// parses each input on its own and concatenates the games, tagging them with their file and the
+/// fingerprint of its lines
+fn parse_inputs(
+    inputs: Vec<LogFile>,
+    scoring: &ScoringRules,
+    jobs: &JobsArgs,
+) -> Result<Vec<Game>, String> {
+    let collectors = Collectors::builtin();
+    let mut parsed = Vec::new();
+    for LogFile { file, lines } in inputs {
+        let fingerprint = input::fingerprint(&lines);
+        let parse = parser::parse_parallel(lines, scoring, &collectors, jobs.threads());
+        let mut games = parse.map_err(|e| {
+            format!(
+                "could not parse {}: {}",
+                file.as_deref().unwrap_or("input"),
+                e
+            )
+        })?;
+        for source in games.iter_mut().filter_map(|game| game.source.as_mut()) {
+            source.file = file.clone();
+            source.fingerprint = Some(fingerprint.clone());
+        }
+        parsed.append(&mut games);
+    }
+    Ok(parsed)
+}
+
+/// writes each table as `<name>.csv` into the given directory, creating it if needed
+fn write_tables(dir: String, tables: Vec<report::csv::Table>) -> Result<(), String> {
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        return Err(format!("could not create directory: {}", e));
+    }
+
+    for table in tables {
+        let path = std::path::Path::new(&dir).join(format!("{}.csv", table.name));
+        write_output(Some(path.to_string_lossy().to_string()), table.content)?;
+    }
+    Ok(())
+}
+
+/// lists the first few line numbers, enough to find them on the log
+fn summarize_lines(lines: &[usize]) -> String {
+    const SHOWN: usize = 5;
+    let shown: Vec<String> = lines.iter().take(SHOWN).map(|l| l.to_string()).collect();
+    if lines.len() > SHOWN {
+        format!("{}, ...", shown.join(", "))
+    } else {
+        shown.join(", ")
+    }
 }
 
 fn write_output(output: Option<String>, content: String) -> Result<(), String> {
@@ -75,7 +639,7 @@ fn write_output(output: Option<String>, content: String) -> Result<(), String> {
                 Err(e) => return Err(format!("could not create file: {}", e)),
             };
 
-            match f.write(content.as_bytes()) {
+            match f.write_all(content.as_bytes()) {
                 Ok(_) => (),
                 Err(e) => return Err(format!("could not write to file: {}", e)),
             }
@@ -83,7 +647,12 @@ fn write_output(output: Option<String>, content: String) -> Result<(), String> {
             Ok(())
         }
         None => {
-            println!("{}", content);
+            // content that already ends its last line doesn't get an empty one after it
+            if content.ends_with('\n') {
+                print!("{}", content);
+            } else {
+                println!("{}", content);
+            }
             Ok(())
         }
     }
@@ -94,15 +663,45 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_read_input() {
-        // Test reading from a file
-        std::fs::write("test_input.txt", "Hello\nworld").unwrap();
-        let result = read_input(Some("test_input.txt".to_string()));
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), vec!["Hello", "world"]);
+    fn test_cli() {
+        // no subcommand behaves like parse
+        let cli = Cli::try_parse_from(["q3a", "--file", "input", "--events"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.parse.input.file, vec!["input"]);
+        assert!(cli.parse.events);
 
-        // Clean up
-        std::fs::remove_file("test_input.txt").unwrap();
+        let cli = Cli::try_parse_from(["q3a", "report", "--format", "html", "--file", "input"]);
+        match cli.unwrap().command {
+            Some(Command::Report(args)) => {
+                assert_eq!(args.format, Format::Html);
+                assert_eq!(args.scoring.scoring, "challenge");
+                assert_eq!(args.jobs.threads(), 1);
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        let cli = Cli::try_parse_from(["q3a", "rank", "--merge", "a.json", "b.json"]).unwrap();
+        match cli.command {
+            Some(Command::Rank(args)) => assert_eq!(args.merge, vec!["a.json", "b.json"]),
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        let cli = Cli::try_parse_from(["q3a", "stats", "--jobs", "0"]).unwrap();
+        match cli.command {
+            Some(Command::Stats(args)) => assert!(args.jobs.threads() >= 1),
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        // split needs a directory to write into
+        assert!(Cli::try_parse_from(["q3a", "split", "--file", "input"]).is_err());
+        // options of the default command can't be mixed with a subcommand
+        assert!(Cli::try_parse_from(["q3a", "--events", "stats"]).is_err());
+    }
+
+    #[test]
+    fn test_summarize_lines() {
+        assert_eq!(summarize_lines(&[2]), "2");
+        assert_eq!(summarize_lines(&[1, 2, 3, 4, 5, 6]), "1, 2, 3, 4, 5, ...");
     }
 
     #[test]
@@ -119,4 +718,41 @@ mod tests {
         // Clean up
         std::fs::remove_file("test_output.txt").unwrap();
     }
+
+    #[test]
+    fn test_write_tables() {
+        let tables = vec![report::csv::Table {
+            name: "games",
+            content: "game\n0\n".to_string(),
+        }];
+        let result = write_tables("test_tables".to_string(), tables);
+        assert!(result.is_ok());
+        assert_eq!(std::fs::read_to_string("test_tables/games.csv").unwrap(), "game\n0\n");
+
+        // Clean up
+        std::fs::remove_dir_all("test_tables").unwrap();
+    }
+
+    #[test]
+    fn test_anonymize_ends_lines() {
+        let cli = Cli::try_parse_from([
+            "q3a",
+            "anonymize",
+            "--file",
+            "input",
+            "--output",
+            "test_anonymized.log",
+        ]);
+        let result = match cli.unwrap().command {
+            Some(Command::Anonymize(args)) => run_anonymize(args),
+            command => panic!("unexpected command {:?}", command),
+        };
+        let content = std::fs::read_to_string("test_anonymized.log").unwrap();
+        std::fs::remove_file("test_anonymized.log").unwrap();
+
+        assert!(result.is_ok());
+        let input = std::fs::read_to_string("input").unwrap();
+        assert!(content.ends_with("------------------------------------------------------------\n"));
+        assert_eq!(content.lines().count(), input.lines().count());
+    }
 }
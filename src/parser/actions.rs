@@ -1,22 +1,60 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use super::game::Game;
+use super::collector::Collectors;
+use super::game::{Game, Source};
+use super::player::Team;
+use super::scoring::ScoringRules;
 
-#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+/// serialized as `{"type": "kill", "data": [killer, killed, means_of_death]}`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum Action {
-    InitGame,
+    /// server settings as a `\key\value` info string
+    InitGame(String),
     Kill(u32, u32, u32),
     ClientConnect(u32),
     ClientBegin(u32),
     ClientUserinfoChanged(u32, String),
     ClientDisconnect(u32),
+    /// item picked up by a client
+    Item(u32, String),
+    /// chat message and the name of the player who sent it
+    Say(String, String),
+    /// chat message to the sender's team and the name of the player who sent it
+    SayTeam(String, String),
+    /// private message: the names of the player who sent it and of the one it was sent to, and
+    /// the message
+    Tell(String, String, String),
     ShutdownGame,
 }
 
+/// an action along with the time it happened, in seconds since the start of the log
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Event {
+    pub time: u32,
+    /// line of the log the action was read from, starting at 1
+    pub line: usize,
+    pub action: Action,
+}
+
 impl Action {
+    /// applies the action to the game, running the game's collectors around it
     pub fn parse(&self, game: &mut Game) -> Result<(), &'static str> {
+        let mut collectors = std::mem::take(&mut game.collectors);
+        collectors.observe(self, game);
+        let result = self.apply(game);
+        let reported = collectors.report(game);
+        game.collectors = collectors;
+        result.and(reported)
+    }
+
+    fn apply(&self, game: &mut Game) -> Result<(), &'static str> {
         match self {
-            Action::InitGame => Ok(()),
+            Action::InitGame(settings) => {
+                game.set_settings(settings);
+                Ok(())
+            }
             Action::ClientConnect(client) => {
                 game.new_player(*client);
                 Ok(())
@@ -32,37 +70,89 @@ impl Action {
                     return Err("Could not parse userinfo");
                 }
 
-                game.rename_player(*player, parts[1].to_string())
+                game.rename_player(*player, parts[1].to_string())?;
+
+                let team = parse_info(metadata)
+                    .get("t")
+                    .and_then(|team| team.parse::<u32>().ok())
+                    .and_then(Team::from_u32);
+                match team {
+                    Some(team) => game.change_team(*player, team),
+                    None => Ok(()),
+                }
             }
             Action::ClientBegin(id) => {
                 game.player_joined(*id)
             },
-            Action::ClientDisconnect(_) => Ok(()),
-            Action::ShutdownGame => Ok(()),
+            Action::ClientDisconnect(id) => game.player_left(*id),
+            Action::Item(id, item) => game.add_item(*id, item.to_string()),
+            Action::Say(player, message) => {
+                game.add_chat(player.to_string(), message.to_string());
+                Ok(())
+            }
+            Action::SayTeam(player, message) => {
+                game.add_team_chat(player.to_string(), message.to_string());
+                Ok(())
+            }
+            Action::Tell(player, recipient, message) => {
+                game.add_private_chat(player.to_string(), recipient.to_string(), message.to_string());
+                Ok(())
+            }
+            Action::ShutdownGame => {
+                game.shutdown();
+                Ok(())
+            }
         }
 
     }
 
-    pub fn parse_game(actions: Vec<Action>) -> Result<Game, &'static str> {
-        let mut game = Game::new();
-        for action in actions {
-            action.parse(&mut game)?;
+    pub fn parse_game(
+        events: Vec<Event>,
+        scoring: &ScoringRules,
+        collectors: &Collectors,
+    ) -> Result<Game, &'static str> {
+        let mut game = Game::with_scoring(scoring.clone());
+        game.collectors = collectors.clone();
+        if let (Some(first), Some(last)) = (events.first(), events.last()) {
+            game.started_at = first.time;
+            game.source = Some(Source {
+                file: None,
+                first_line: first.line,
+                last_line: last.line,
+                fingerprint: None,
+            });
+        }
+        for event in events {
+            game.clock = event.time;
+            event.action.parse(&mut game)?;
         }
+        // games cut short have no ShutdownGame, so close any sessions left open
+        game.shutdown();
 
         Ok(game)
     }
 }
 
+/// parses an info string, a flat list of `key\value` pairs such as the userinfo or server settings
+pub fn parse_info(info: &str) -> HashMap<String, String> {
+    info.trim_start_matches('\\')
+        .split('\\')
+        .collect::<Vec<&str>>()
+        .chunks_exact(2)
+        .map(|pair| (pair[0].to_string(), pair[1].to_string()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::parser::player::Player;
+    use crate::parser::player::{Player, Session, Team};
     use super::*;
     use crate::parser::game::Game;
 
     #[test]
     fn test_parse_init_game() {
         let mut game = Game::new();
-        let action = Action::InitGame;
+        let action = Action::InitGame(String::new());
         assert_eq!(action.parse(&mut game), Ok(()));
     }
 
@@ -97,19 +187,36 @@ mod tests {
         assert_eq!(game.players[0].name, "Test");
     }
 
+    #[test]
+    fn test_parse_client_userinfo_changed_team() {
+        let mut game = Game::new();
+        game.new_player(1);
+        let action = Action::ClientUserinfoChanged(1, "n\\Test\\t\\2\\model\\sarge".to_string());
+        action.parse(&mut game).unwrap();
+        assert_eq!(game.players[0].team, Team::Blue);
+    }
+
+    #[test]
+    fn test_parse_info() {
+        let info = parse_info("\\sv_hostname\\Code Miner Server\\g_gametype\\0\\mapname\\q3dm17\\dangling");
+        assert_eq!(info.len(), 3);
+        assert_eq!(info["sv_hostname"], "Code Miner Server");
+        assert_eq!(info["mapname"], "q3dm17");
+    }
+
     #[test]
     fn test_parse_client_begin() {
         let mut game = Game::new();
         game.new_player(1);
         let action = Action::ClientBegin(1);
         action.parse(&mut game).unwrap();
-        assert_eq!(game.players[0].joined, true);
+        assert!(game.players[0].joined);
     }
 
     #[test]
     fn test_to_game() {
         let actions = vec![
-                Action::InitGame,
+                Action::InitGame(String::new()),
                 Action::ClientConnect(2),
                 Action::ClientConnect(3),
                 Action::ClientConnect(4),
@@ -127,21 +234,30 @@ mod tests {
                 name: "Testing".to_string(),
                 id: 2,
                 joined: true,
+                team: Team::Free,
+                team_history: vec![],
+                sessions: vec![Session { start: 0, end: Some(0) }],
             },
             Player {
                 name: "Test".to_string(),
                 id: 3,
                 joined: true,
+                team: Team::Free,
+                team_history: vec![],
+                sessions: vec![Session { start: 0, end: Some(0) }],
             },
             Player {
                 name: "".to_string(),
                 id: 4,
                 joined: false,
+                team: Team::Free,
+                team_history: vec![],
+                sessions: vec![],
             },
         ];
         let expected_player_list = vec!["Testing".to_string(), "Test".to_string()];
 
-        let game = Action::parse_game(actions).unwrap();
+        let game = Action::parse_game(at_zero(actions), &ScoringRules::default(), &Collectors::builtin()).unwrap();
         assert_eq!(game.players, expected_players);
         assert_eq!(game.player_list, expected_player_list);
     }
@@ -150,12 +266,23 @@ mod tests {
     #[should_panic]
     fn test_invalid_game() {
         let actions= vec![
-                Action::InitGame,
+                Action::InitGame(String::new()),
                 Action::ClientConnect(2),
                 Action::Kill(3, 2, 1),
                 Action::ShutdownGame,
             ];
 
-        Action::parse_game(actions).unwrap();
+        Action::parse_game(at_zero(actions), &ScoringRules::default(), &Collectors::builtin()).unwrap();
+    }
+
+    fn at_zero(actions: Vec<Action>) -> Vec<Event> {
+        actions
+            .into_iter()
+            .map(|action| Event {
+                time: 0,
+                line: 0,
+                action,
+            })
+            .collect()
     }
 }
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+use super::game::KillMode;
+
+/// seconds between two frags for the second one to earn an "Excellent"
+pub const EXCELLENT_WINDOW: u32 = 2;
+
+/// medals handed out by the game, derived from a player's kills in a single game
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Awards {
+    /// two frags within [EXCELLENT_WINDOW] seconds
+    pub excellent: u32,
+    /// consecutive railgun frags
+    pub impressive: u32,
+    /// gauntlet frags, announced as "Humiliation"
+    pub gauntlet: u32,
+    /// made at least one frag and never died during the game
+    pub perfect: bool,
+    #[serde(skip)]
+    pub died: bool,
+    #[serde(skip)]
+    pub last_frag: Option<u32>,
+    #[serde(skip)]
+    pub last_frag_railgun: bool,
+}
+
+impl Awards {
+    pub fn add_frag(&mut self, time: u32, mode: &KillMode) {
+        if let Some(last) = self.last_frag {
+            if time.saturating_sub(last) <= EXCELLENT_WINDOW {
+                self.excellent += 1;
+            }
+        }
+        self.last_frag = Some(time);
+
+        let railgun = *mode == KillMode::ModRailgun;
+        if railgun && self.last_frag_railgun {
+            self.impressive += 1;
+        }
+        self.last_frag_railgun = railgun;
+
+        if *mode == KillMode::ModGauntlet {
+            self.gauntlet += 1;
+        }
+
+        self.perfect = !self.died;
+    }
+
+    pub fn add_death(&mut self) {
+        self.died = true;
+        self.perfect = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_awards() {
+        let mut awards = Awards::default();
+        awards.add_frag(10, &KillMode::ModRailgun);
+        awards.add_frag(12, &KillMode::ModRailgun);
+        awards.add_frag(20, &KillMode::ModGauntlet);
+        awards.add_frag(30, &KillMode::ModRailgun);
+
+        assert_eq!(awards.excellent, 1);
+        assert_eq!(awards.impressive, 1);
+        assert_eq!(awards.gauntlet, 1);
+        assert!(awards.perfect);
+
+        awards.add_death();
+        awards.add_frag(40, &KillMode::ModRailgun);
+        assert_eq!(awards.impressive, 2);
+        assert!(!awards.perfect);
+    }
+}
@@ -0,0 +1,291 @@
+use std::fmt;
+
+use super::actions::Action;
+use super::game::{Game, KillMode, WORLD};
+
+/// gathers statistics while a game is being built
+///
+/// every action goes through [StatCollector::observe] before it's applied to the game, so ids
+/// still resolve to the players involved (see [Game::player]) and `game.clock` holds the time of
+/// the event. [StatCollector::report] runs right after the action is applied and merges whatever
+/// was collected into the game, usually through [Game::set_stat] so it ends up on the report.
+///
+/// collectors are `Send` so games can be built on several threads, see
+/// [parse_parallel](crate::parser::parse_parallel).
+///
+/// # Example
+/// ```
+/// use q3a_log_parser::parser::collector::{Collectors, StatCollector};
+/// use q3a_log_parser::{parse_with, Action, Game, ScoringRules};
+///
+/// /// counts the items picked up on each game
+/// #[derive(Default)]
+/// struct ItemCount(u32);
+///
+/// impl StatCollector for ItemCount {
+///     fn observe(&mut self, action: &Action, _game: &Game) {
+///         if let Action::Item(..) = action {
+///             self.0 += 1;
+///         }
+///     }
+///
+///     fn report(&mut self, game: &mut Game) -> Result<(), &'static str> {
+///         game.set_stat("items", self.0)
+///     }
+/// }
+///
+/// let lines = vec![
+///     "  0:00 InitGame: \\mapname\\q3dm17".to_string(),
+///     "  0:01 ClientConnect: 2".to_string(),
+///     "  0:01 ClientUserinfoChanged: 2 n\\Isgalamido\\t\\0".to_string(),
+///     "  0:02 Item: 2 weapon_rocketlauncher".to_string(),
+///     "  0:03 ShutdownGame:".to_string(),
+/// ];
+/// let mut collectors = Collectors::builtin();
+/// collectors.register::<ItemCount>();
+///
+/// let games = parse_with(lines, &ScoringRules::default(), &collectors).unwrap();
+/// assert_eq!(games[0].stats["items"], 1);
+/// ```
+pub trait StatCollector: Send {
+    fn observe(&mut self, action: &Action, game: &Game);
+    /// a failure, such as a statistic [Game::set_stat] refuses, stops the game from being built
+    fn report(&mut self, game: &mut Game) -> Result<(), &'static str>;
+}
+
+/// the collectors attached to a game
+///
+/// cloning gives a set of fresh collectors, so one set can be used as the template for every game
+#[derive(Default)]
+pub struct Collectors {
+    registered: Vec<Registered>,
+}
+
+struct Registered {
+    new: fn() -> Box<dyn StatCollector>,
+    collector: Box<dyn StatCollector>,
+}
+
+impl Collectors {
+    /// the collectors every game needs: [KillScore] and [MeansOfDeath]
+    pub fn builtin() -> Collectors {
+        let mut collectors = Collectors::default();
+        collectors.register::<KillScore>();
+        collectors.register::<MeansOfDeath>();
+        collectors
+    }
+
+    pub fn register<C: StatCollector + Default + 'static>(&mut self) {
+        fn new<C: StatCollector + Default + 'static>() -> Box<dyn StatCollector> {
+            Box::<C>::default()
+        }
+
+        self.registered.push(Registered {
+            new: new::<C>,
+            collector: new::<C>(),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.registered.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registered.is_empty()
+    }
+
+    pub fn observe(&mut self, action: &Action, game: &Game) {
+        for registered in self.registered.iter_mut() {
+            registered.collector.observe(action, game);
+        }
+    }
+
+    /// reports every collector, even after one of them fails, returning the first failure
+    pub fn report(&mut self, game: &mut Game) -> Result<(), &'static str> {
+        let mut result = Ok(());
+        for registered in self.registered.iter_mut() {
+            let reported = registered.collector.report(game);
+            result = result.and(reported);
+        }
+        result
+    }
+}
+
+impl Clone for Collectors {
+    fn clone(&self) -> Self {
+        Collectors {
+            registered: self
+                .registered
+                .iter()
+                .map(|registered| Registered {
+                    new: registered.new,
+                    collector: (registered.new)(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Debug for Collectors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Collectors")
+            .field("registered", &self.registered.len())
+            .finish()
+    }
+}
+
+/// scores each kill into `Game::kill_score` according to the game's scoring rules
+#[derive(Default)]
+pub struct KillScore {
+    pending: Vec<(String, i32)>,
+}
+
+impl StatCollector for KillScore {
+    fn observe(&mut self, action: &Action, game: &Game) {
+        let (killer_id, killed_id) = match action {
+            Action::Kill(killer, killed, _) => (*killer, *killed),
+            _ => return,
+        };
+        let victim = match game.player(killed_id) {
+            Some(p) => p,
+            None => return,
+        };
+
+        let scoring = &game.scoring;
+        if killer_id == WORLD {
+            self.pending
+                .push((victim.name.clone(), scoring.world_death));
+        } else if killer_id == killed_id {
+            self.pending.push((victim.name.clone(), scoring.suicide));
+        } else if let Some(killer) = game.player(killer_id) {
+            let team_kill = killer.team.is_playing_side() && victim.team == killer.team;
+            // the first blood bonus goes to the first kill of an opponent
+            let points = if team_kill {
+                scoring.team_kill
+            } else if game.first_blood.is_none() {
+                scoring.kill + scoring.first_blood
+            } else {
+                scoring.kill
+            };
+            self.pending.push((killer.name.clone(), points));
+        }
+    }
+
+    fn report(&mut self, game: &mut Game) -> Result<(), &'static str> {
+        for (name, points) in self.pending.drain(..) {
+            if points != 0 {
+                *game.kill_score.entry(name).or_insert(0) += points;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// counts each kill into `Game::means_of_death`
+#[derive(Default)]
+pub struct MeansOfDeath {
+    pending: Option<KillMode>,
+}
+
+impl StatCollector for MeansOfDeath {
+    fn observe(&mut self, action: &Action, _game: &Game) {
+        if let Action::Kill(_, _, means_of_death) = action {
+            self.pending = Some(KillMode::from_u32(*means_of_death));
+        }
+    }
+
+    fn report(&mut self, game: &mut Game) -> Result<(), &'static str> {
+        if let Some(mode) = self.pending.take() {
+            *game.means_of_death.entry(mode).or_insert(0) += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct ChatLines(u32);
+
+    impl StatCollector for ChatLines {
+        fn observe(&mut self, action: &Action, _game: &Game) {
+            if let Action::Say(..) = action {
+                self.0 += 1;
+            }
+        }
+
+        fn report(&mut self, game: &mut Game) -> Result<(), &'static str> {
+            game.set_stat("chat_lines", self.0)
+        }
+    }
+
+    #[test]
+    fn test_custom_collector() -> Result<(), &'static str> {
+        let mut game = Game::new();
+        game.collectors.register::<ChatLines>();
+        Action::ClientConnect(1).parse(&mut game)?;
+        Action::Say("Zeh".to_string(), "gg".to_string()).parse(&mut game)?;
+        Action::Say("Zeh".to_string(), "rematch?".to_string()).parse(&mut game)?;
+
+        assert_eq!(game.stats["chat_lines"], 2);
+        let json = serde_json::to_value(&game).unwrap();
+        assert_eq!(json["chat_lines"], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_collectors_clone_fresh() {
+        let mut collectors = Collectors::builtin();
+        collectors.register::<ChatLines>();
+        assert_eq!(collectors.len(), 3);
+
+        let mut game = Game::new();
+        collectors.observe(&Action::Say("Zeh".to_string(), "gg".to_string()), &game);
+        let mut fresh = collectors.clone();
+        fresh.report(&mut game).unwrap();
+        assert_eq!(game.stats["chat_lines"], 0);
+
+        collectors.report(&mut game).unwrap();
+        assert_eq!(game.stats["chat_lines"], 1);
+    }
+
+    #[test]
+    fn test_without_builtin_collectors() -> Result<(), &'static str> {
+        let mut game = Game::new();
+        game.collectors = Collectors::default();
+        for id in [1, 2] {
+            Action::ClientConnect(id).parse(&mut game)?;
+            Action::ClientBegin(id).parse(&mut game)?;
+        }
+        Action::Kill(1, 2, 10).parse(&mut game)?;
+
+        assert_eq!(game.total_kills, 1);
+        assert!(game.kill_score.is_empty());
+        assert!(game.means_of_death.is_empty());
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct Kills;
+
+    impl StatCollector for Kills {
+        fn observe(&mut self, _action: &Action, _game: &Game) {}
+
+        fn report(&mut self, game: &mut Game) -> Result<(), &'static str> {
+            game.set_stat("kills", 0)
+        }
+    }
+
+    #[test]
+    fn test_collector_cannot_replace_builtin_fields() {
+        let mut game = Game::new();
+        game.collectors.register::<Kills>();
+        assert_eq!(
+            Action::ClientConnect(1).parse(&mut game),
+            Err("statistic name is taken by a built-in field")
+        );
+        assert!(game.stats.is_empty());
+    }
+}
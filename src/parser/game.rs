@@ -1,11 +1,17 @@
-use super::player::Player;
-use serde::Serialize;
-use std::collections::HashMap;
+use super::actions::parse_info;
+use super::awards::Awards;
+use super::collector::Collectors;
+use super::player::{PlayTime, Player, Players, Team};
+use super::scoring::ScoringRules;
+use super::streaks::{EndedStreak, FirstBlood, StreakStats, MIN_REPORTED_STREAK};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 pub const WORLD: u32 = 1022;
 
 #[warn(dead_code)]
-#[derive(Clone, Debug, Serialize, PartialEq, Eq, Hash)]
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum KillMode {
     ModUnknown,
@@ -40,7 +46,7 @@ pub enum KillMode {
 }
 
 impl KillMode {
-    fn from_u32(n: u32) -> KillMode {
+    pub fn from_u32(n: u32) -> KillMode {
         match n {
             0 => KillMode::ModUnknown,
             1 => KillMode::ModShotgun,
@@ -74,9 +80,144 @@ impl KillMode {
             _ => KillMode::ModUnknown,
         }
     }
+
+    /// name of the means of death as printed on the log
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KillMode::ModUnknown => "MOD_UNKNOWN",
+            KillMode::ModShotgun => "MOD_SHOTGUN",
+            KillMode::ModGauntlet => "MOD_GAUNTLET",
+            KillMode::ModMachinegun => "MOD_MACHINEGUN",
+            KillMode::ModGrenade => "MOD_GRENADE",
+            KillMode::ModGrenadeSplash => "MOD_GRENADE_SPLASH",
+            KillMode::ModRocket => "MOD_ROCKET",
+            KillMode::ModRocketSplash => "MOD_ROCKET_SPLASH",
+            KillMode::ModPlasma => "MOD_PLASMA",
+            KillMode::ModPlasmaSplash => "MOD_PLASMA_SPLASH",
+            KillMode::ModRailgun => "MOD_RAILGUN",
+            KillMode::ModLightning => "MOD_LIGHTNING",
+            KillMode::ModBfg => "MOD_BFG",
+            KillMode::ModBfgSplash => "MOD_BFG_SPLASH",
+            KillMode::ModWater => "MOD_WATER",
+            KillMode::ModSlime => "MOD_SLIME",
+            KillMode::ModLava => "MOD_LAVA",
+            KillMode::ModCrush => "MOD_CRUSH",
+            KillMode::ModTelefrag => "MOD_TELEFRAG",
+            KillMode::ModFalling => "MOD_FALLING",
+            KillMode::ModSuicide => "MOD_SUICIDE",
+            KillMode::ModTargetLaser => "MOD_TARGET_LASER",
+            KillMode::ModTriggerHurt => "MOD_TRIGGER_HURT",
+            KillMode::ModNail => "MOD_NAIL",
+            KillMode::ModChaingun => "MOD_CHAINGUN",
+            KillMode::ModProximityMine => "MOD_PROXIMITY_MINE",
+            KillMode::ModKamikaze => "MOD_KAMIKAZE",
+            KillMode::ModJuiced => "MOD_JUICED",
+            KillMode::ModGrapple => "MOD_GRAPPLE",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KillRecord {
+    pub time: u32,
+    /// name of the killer, `<world>` for environment deaths
+    pub killer: String,
+    pub victim: String,
+    pub means_of_death: KillMode,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ItemPickup {
+    pub time: u32,
+    pub player: String,
+    pub item: String,
+}
+
+/// who a chat message was sent to
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    /// everyone on the server, sent with `say`
+    #[default]
+    All,
+    /// the sender's team, sent with `sayteam`
+    Team,
+    /// a single player, sent with `tell`
+    Private,
+}
+
+impl Channel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::All => "all",
+            Channel::Team => "team",
+            Channel::Private => "private",
+        }
+    }
+
+    fn is_all(&self) -> bool {
+        *self == Channel::All
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChatMessage {
+    pub time: u32,
+    pub player: String,
+    pub message: String,
+    // left out for messages to everyone, so they're written the same as before channels
+    #[serde(default, skip_serializing_if = "Channel::is_all")]
+    pub channel: Channel,
+    /// the player a private message was sent to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+}
+
+/// where a game was read from
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Source {
+    /// log file the game was read from, none when reading from stdin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// first line of the game on the log, starting at 1
+    pub first_line: usize,
+    pub last_line: usize,
+    /// fingerprint of the whole log the game was read from, see
+    /// [input::fingerprint](crate::input::fingerprint). none when the games were parsed
+    /// without one, such as by the library or by builds from before it was added
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TeamStats {
+    pub players: Vec<String>,
+    /// kills made by the team's players on the opposing team
+    pub kills: u32,
+    /// kills made by the team's players on their own teammates
+    pub team_kills: u32,
 }
 
-#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+/// names the game's own fields are reported under, along with the `game` index added by the
+/// ndjson format. statistics are flattened next to them, so they can't take these names
+pub const BUILTIN_FIELDS: &[&str] = &[
+    "game",
+    "total_kills",
+    "players",
+    "kills",
+    "means_of_death",
+    "teams",
+    "first_blood",
+    "streaks",
+    "ended_streaks",
+    "awards",
+    "play_time",
+    "source",
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+// missing fields are left empty, so reports written before a field was added still load
+#[serde(default)]
 pub struct Game {
     pub total_kills: u32,
     #[serde(rename = "players")]
@@ -84,56 +225,314 @@ pub struct Game {
     #[serde(rename = "kills")]
     pub kill_score: HashMap<String, i32>,
     pub means_of_death: HashMap<KillMode, u32>,
-    #[serde(skip_serializing)]
-    pub players: Vec<Player>,
+    /// per team statistics. only filled on team based games (TDM/CTF)
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub teams: HashMap<Team, TeamStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_blood: Option<FirstBlood>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub streaks: HashMap<String, StreakStats>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ended_streaks: Vec<EndedStreak>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub awards: HashMap<String, Awards>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub play_time: HashMap<String, PlayTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    /// statistics reported by custom collectors, keyed by name
+    #[serde(flatten)]
+    pub stats: BTreeMap<String, serde_json::Value>,
+    /// every kill in the order they happened
+    #[serde(skip)]
+    pub kill_feed: Vec<KillRecord>,
+    /// server settings sent on `InitGame`
+    #[serde(skip)]
+    pub settings: HashMap<String, String>,
+    #[serde(skip)]
+    pub items: Vec<ItemPickup>,
+    #[serde(skip)]
+    pub chat: Vec<ChatMessage>,
+    #[serde(skip)]
+    pub players: Players,
+    #[serde(skip)]
+    pub scoring: ScoringRules,
+    /// time of the event currently being parsed, in seconds
+    #[serde(skip)]
+    pub clock: u32,
+    #[serde(skip)]
+    pub started_at: u32,
+    #[serde(skip)]
+    pub ended_at: u32,
+    /// collectors run on every action applied to the game
+    #[serde(skip)]
+    pub collectors: Collectors,
+}
+
+// collectors hold nothing but what they're about to report, so they're left out when comparing
+// games. the fields are listed one by one so a new field can't be forgotten
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        let Game {
+            total_kills,
+            player_list,
+            kill_score,
+            means_of_death,
+            teams,
+            first_blood,
+            streaks,
+            ended_streaks,
+            awards,
+            play_time,
+            source,
+            stats,
+            kill_feed,
+            settings,
+            items,
+            chat,
+            players,
+            scoring,
+            clock,
+            started_at,
+            ended_at,
+            collectors: _,
+        } = self;
+        *total_kills == other.total_kills
+            && *player_list == other.player_list
+            && *kill_score == other.kill_score
+            && *means_of_death == other.means_of_death
+            && *teams == other.teams
+            && *first_blood == other.first_blood
+            && *streaks == other.streaks
+            && *ended_streaks == other.ended_streaks
+            && *awards == other.awards
+            && *play_time == other.play_time
+            && *source == other.source
+            && *stats == other.stats
+            && *kill_feed == other.kill_feed
+            && *settings == other.settings
+            && *items == other.items
+            && *chat == other.chat
+            && *players == other.players
+            && *scoring == other.scoring
+            && *clock == other.clock
+            && *started_at == other.started_at
+            && *ended_at == other.ended_at
+    }
+}
+
+impl Eq for Game {}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Game {
+        Game::with_scoring(ScoringRules::default())
+    }
+
+    pub fn with_scoring(scoring: ScoringRules) -> Game {
         Game {
             total_kills: 0,
             player_list: Vec::new(),
             kill_score: HashMap::new(),
             means_of_death: HashMap::new(),
-            players: Vec::new(),
+            teams: HashMap::new(),
+            first_blood: None,
+            streaks: HashMap::new(),
+            ended_streaks: Vec::new(),
+            awards: HashMap::new(),
+            play_time: HashMap::new(),
+            source: None,
+            stats: BTreeMap::new(),
+            kill_feed: Vec::new(),
+            settings: HashMap::new(),
+            items: Vec::new(),
+            chat: Vec::new(),
+            players: Players::new(),
+            scoring,
+            clock: 0,
+            started_at: 0,
+            ended_at: 0,
+            collectors: Collectors::builtin(),
         }
     }
 
+    pub fn player(&self, id: u32) -> Option<&Player> {
+        self.players.slot(id)
+    }
+
+    pub fn player_name(&self, id: u32) -> Option<&str> {
+        self.player(id).map(|p| p.name.as_str())
+    }
+
+    /// sets a statistic to be reported along with the game, replacing any previous value
+    ///
+    /// fails if the name is one of the [BUILTIN_FIELDS] or the value can't be written as JSON,
+    /// such as a map with keys that aren't strings
+    pub fn set_stat<T: Serialize>(&mut self, name: &str, value: T) -> Result<(), &'static str> {
+        if BUILTIN_FIELDS.contains(&name) {
+            return Err("statistic name is taken by a built-in field");
+        }
+        let value = match serde_json::to_value(value) {
+            Ok(value) => value,
+            Err(_) => return Err("statistic is not representable as JSON"),
+        };
+        self.stats.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    pub fn set_settings(&mut self, info: &str) {
+        self.settings = parse_info(info);
+    }
+
+    /// name of the map the game was played on, if the server sent it
+    pub fn map(&self) -> Option<&str> {
+        self.settings.get("mapname").map(|map| map.as_str())
+    }
+
+    /// how long the game lasted, in seconds
+    pub fn duration(&self) -> u32 {
+        self.ended_at.saturating_sub(self.started_at)
+    }
+
+    /// the team with the most kills on team based games, otherwise the player with the highest score
+    pub fn winner(&self) -> Option<String> {
+        if !self.teams.is_empty() {
+            let mut teams: Vec<(&Team, &TeamStats)> = self.teams.iter().collect();
+            teams.sort_by_key(|(team, stats)| (std::cmp::Reverse(stats.kills), **team as u8));
+            return teams.first().map(|(team, _)| team.as_str().to_string());
+        }
+
+        self.kill_score
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(name, _)| name.clone())
+    }
+
+    /// records an item picked up by the client. pickups by clients that never connected are
+    /// skipped, they're only kept for the exports
+    pub fn add_item(&mut self, id: u32, item: String) -> Result<(), &'static str> {
+        let player = match self.players.slot(id) {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        self.items.push(ItemPickup {
+            time: self.clock,
+            player: player.name.clone(),
+            item,
+        });
+        Ok(())
+    }
+
+    pub fn add_chat(&mut self, player: String, message: String) {
+        self.chat.push(ChatMessage {
+            time: self.clock,
+            player,
+            message,
+            channel: Channel::All,
+            recipient: None,
+        });
+    }
+
+    pub fn add_team_chat(&mut self, player: String, message: String) {
+        self.chat.push(ChatMessage {
+            time: self.clock,
+            player,
+            message,
+            channel: Channel::Team,
+            recipient: None,
+        });
+    }
+
+    pub fn add_private_chat(&mut self, player: String, recipient: String, message: String) {
+        self.chat.push(ChatMessage {
+            time: self.clock,
+            player,
+            message,
+            channel: Channel::Private,
+            recipient: Some(recipient),
+        });
+    }
+
     pub fn new_player(&mut self, id: u32) {
-        self.players.push(Player::new(id));
+        self.players.connect(id);
+        // listed as an empty name until the player's lists are next updated
         self.player_list.push("".to_string());
     }
 
     pub fn player_joined(&mut self, id: u32) -> Result<(), &'static str> {
-        match self.players.iter_mut().find(|p| p.id == id) {
-            Some(p) => p.joined = true,
+        match self.players.slot_mut(id) {
+            Some(p) => {
+                p.joined = true;
+                p.begin_session(self.clock);
+            }
             None => return Err("Player not found"),
         }
 
-        self.rebuild_player_list();
+        self.update_lists(id);
+        Ok(())
+    }
+
+    /// ends the player's current session; their stats are kept on the game. a client that
+    /// never connected has no session to end, so its disconnect is ignored
+    pub fn player_left(&mut self, id: u32) -> Result<(), &'static str> {
+        let player = match self.players.slot_mut(id) {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        if let Some(seconds) = player.end_session(self.clock) {
+            let name = player.name.clone();
+            self.play_time.entry(name).or_default().seconds += seconds;
+        }
         Ok(())
     }
 
+    /// ends every open session. called when the game shuts down or the log ends abruptly
+    pub fn shutdown(&mut self) {
+        self.ended_at = self.clock;
+        for player in self.players.iter_mut() {
+            if let Some(seconds) = player.end_session(self.clock) {
+                let name = player.name.clone();
+                self.play_time.entry(name).or_default().seconds += seconds;
+            }
+        }
+    }
+
     pub fn rename_player(&mut self, id: u32, name: String) -> Result<(), &'static str> {
-        match self.players.iter_mut().find(|p| p.id == id) {
+        match self.players.slot_mut(id) {
             Some(p) => p.name = name,
             None => return Err("Player not found"),
         }
 
-        self.rebuild_player_list();
+        self.update_lists(id);
         Ok(())
     }
 
-    fn rebuild_player_list(&mut self) {
-        self.player_list.clear();
-        for player in &self.players {
-            if player.joined {
-                self.player_list.push(player.name.clone());
-            }
+    pub fn change_team(&mut self, id: u32, team: Team) -> Result<(), &'static str> {
+        match self.players.slot_mut(id) {
+            Some(p) => p.change_team(team),
+            None => return Err("Player not found"),
         }
+
+        self.update_lists(id);
+        Ok(())
     }
 
-    pub fn add_kill(
+    fn update_lists(&mut self, id: u32) {
+        self.players
+            .update_lists(id, &mut self.player_list, &mut self.teams);
+    }
+
+    /// records a kill. scores and means of death are counted by the collectors, see
+    /// [KillScore](super::collector::KillScore) and [MeansOfDeath](super::collector::MeansOfDeath),
+    /// so kills are applied with [Action::parse](super::actions::Action::parse), which runs them
+    pub(crate) fn add_kill(
         &mut self,
         killer_id: u32,
         killed_id: u32,
@@ -141,43 +540,95 @@ impl Game {
     ) -> Result<(), &'static str> {
         self.total_kills += 1;
 
-        if killer_id == WORLD {
-            let player = match self.players.iter().find(|p| p.id == killed_id) {
+        let (victim, ended_by) = if killer_id == WORLD {
+            let player = match self.players.slot(killed_id) {
                 Some(p) => p,
                 None => return Err("Killed player not found"),
             };
-            match self.kill_score.get_mut(&player.name) {
-                Some(score) => *score -= 1,
-                None => {
-                    self.kill_score.insert(player.name.clone(), -1);
-                }
+            (player.name.clone(), "<world>".to_string())
+        } else if killed_id == killer_id {
+            let player = match self.players.slot(killer_id) {
+                Some(p) => p,
+                None => return Err("Killer player not found"),
             };
-        } else if killed_id != killer_id {
-            let player = match self.players.iter().find(|p| p.id == killer_id) {
+            (player.name.clone(), player.name.clone())
+        } else {
+            let player = match self.players.slot(killer_id) {
                 Some(p) => p,
                 None => return Err("Killer player not found"),
             };
-            match self.kill_score.get_mut(&player.name) {
-                Some(score) => *score += 1,
-                None => {
-                    self.kill_score.insert(player.name.clone(), 1);
-                }
+            let killed = match self.players.slot(killed_id) {
+                Some(p) => p,
+                None => return Err("Killed player not found"),
             };
-        }
+            let (name, team) = (player.name.clone(), player.team);
+            let killed_name = killed.name.clone();
+            let team_kill = team.is_playing_side() && killed.team == team;
+
+            // killing a teammate doesn't draw first blood
+            if !team_kill {
+                if self.first_blood.is_none() {
+                    self.first_blood = Some(FirstBlood {
+                        killer: name.clone(),
+                        victim: killed_name.clone(),
+                        time: self.clock,
+                    });
+                }
+                self.streaks
+                    .entry(name.clone())
+                    .or_default()
+                    .add_kill(self.clock, self.scoring.multi_kill_window);
+                self.awards
+                    .entry(name.clone())
+                    .or_default()
+                    .add_frag(self.clock, &KillMode::from_u32(means_of_death));
+                self.play_time.entry(name.clone()).or_default().kills += 1;
+            }
+
+            if team.is_playing_side() {
+                let stats = self.teams.entry(team).or_default();
+                if team_kill {
+                    stats.team_kills += 1;
+                } else {
+                    stats.kills += 1;
+                }
+            }
 
-        self.add_kill_mode(KillMode::from_u32(means_of_death));
+            (killed_name, name)
+        };
+        self.awards.entry(victim.clone()).or_default().add_death();
+        self.play_time.entry(victim.clone()).or_default().deaths += 1;
+        self.kill_feed.push(KillRecord {
+            time: self.clock,
+            killer: ended_by.clone(),
+            victim: victim.clone(),
+            means_of_death: KillMode::from_u32(means_of_death),
+        });
+        self.end_streak(victim, ended_by);
         Ok(())
     }
 
-    fn add_kill_mode(&mut self, mode: KillMode) {
-        let count = self.means_of_death.get(&mode.clone()).unwrap_or(&0);
-        self.means_of_death.insert(mode.clone(), count + 1);
+    fn end_streak(&mut self, player: String, ended_by: String) {
+        let kills = match self.streaks.get_mut(&player) {
+            Some(stats) => stats.end_streak(),
+            None => return,
+        };
+
+        if kills >= MIN_REPORTED_STREAK {
+            self.ended_streaks.push(EndedStreak {
+                player,
+                kills,
+                ended_by,
+                time: self.clock,
+            });
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::actions::Action;
 
     #[test]
     fn test_new_player() {
@@ -192,16 +643,25 @@ mod tests {
                 name: "".to_string(),
                 id: 1,
                 joined: false,
+                team: Team::Free,
+                team_history: vec![],
+                sessions: vec![],
             },
             Player {
                 name: "".to_string(),
                 id: 2,
                 joined: false,
+                team: Team::Free,
+                team_history: vec![],
+                sessions: vec![],
             },
             Player {
                 name: "".to_string(),
                 id: 3,
                 joined: false,
+                team: Team::Free,
+                team_history: vec![],
+                sessions: vec![],
             },
         ];
 
@@ -216,20 +676,26 @@ mod tests {
         game.new_player(1);
         game.new_player(2);
 
-        game.add_kill(1, 2, 1)?;
-        game.add_kill(2, 1, 1)?;
-        game.add_kill(WORLD, 1, 1)?;
+        Action::Kill(1, 2, 1).parse(&mut game)?;
+        Action::Kill(2, 1, 1).parse(&mut game)?;
+        Action::Kill(WORLD, 1, 1).parse(&mut game)?;
 
         let expected_players = vec![
             Player {
                 name: "".to_string(),
                 id: 1,
                 joined: false,
+                team: Team::Free,
+                team_history: vec![],
+                sessions: vec![],
             },
             Player {
                 name: "".to_string(),
                 id: 2,
                 joined: false,
+                team: Team::Free,
+                team_history: vec![],
+                sessions: vec![],
             },
         ];
 
@@ -245,9 +711,9 @@ mod tests {
         game.new_player(1);
         game.new_player(2);
 
-        game.add_kill(1, 2, 1)?;
-        game.add_kill(2, 1, 1)?;
-        game.add_kill(WORLD, 1, 0)?;
+        Action::Kill(1, 2, 1).parse(&mut game)?;
+        Action::Kill(2, 1, 1).parse(&mut game)?;
+        Action::Kill(WORLD, 1, 0).parse(&mut game)?;
 
         let mut expected_modes = HashMap::new();
         expected_modes.insert(KillMode::ModShotgun, 2);
@@ -267,10 +733,290 @@ mod tests {
         game.rename_player(2, "Testman".to_string()).unwrap();
         game.player_joined(2).unwrap();
 
-        game.add_kill(2, 1, 10).unwrap();
+        Action::Kill(2, 1, 10).parse(&mut game).unwrap();
 
         let json = serde_json::to_string(&game).unwrap();
-        let expected = r#"{"total_kills":1,"players":["TestGuy","Testman"],"kills":{"Testman":1},"means_of_death":{"MOD_RAILGUN":1}}"#;
+        let expected = r#"{"total_kills":1,"players":["TestGuy","Testman"],"kills":{"Testman":1},"means_of_death":{"MOD_RAILGUN":1},"first_blood":{"killer":"Testman","victim":"TestGuy","time":0},"streaks":{"Testman":{"longest_streak":1,"double_kills":0,"triple_kills":0,"multi_kills":0}},"awards":{"TestGuy":{"excellent":0,"impressive":0,"gauntlet":0,"perfect":false},"Testman":{"excellent":0,"impressive":0,"gauntlet":0,"perfect":true}},"play_time":{"TestGuy":{"seconds":0,"kills":0,"deaths":1,"kills_per_minute":0.0,"deaths_per_minute":0.0},"Testman":{"seconds":0,"kills":1,"deaths":0,"kills_per_minute":0.0,"deaths_per_minute":0.0}}}"#;
+        // maps with more than one entry have no fixed order, so compare the parsed values
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(expected).unwrap();
         assert_eq!(json, expected);
     }
+
+    #[test]
+    fn test_set_stat() {
+        let mut game = Game::new();
+        assert_eq!(game.set_stat("chat_lines", 2), Ok(()));
+        assert_eq!(
+            game.set_stat("kills", 2),
+            Err("statistic name is taken by a built-in field")
+        );
+        let pairs: HashMap<(u32, u32), u32> = [((1, 2), 3)].into_iter().collect();
+        assert_eq!(
+            game.set_stat("pairs", pairs),
+            Err("statistic is not representable as JSON")
+        );
+        assert_eq!(game.stats.len(), 1);
+    }
+
+    #[test]
+    fn test_games_compare_without_collectors() {
+        let mut game = Game::new();
+        let mut other = Game::new();
+        other.collectors = Collectors::default();
+        assert_eq!(game, other);
+
+        game.set_stat("chat_lines", 2).unwrap();
+        assert_ne!(game, other);
+    }
+
+    #[test]
+    fn test_team_stats() -> Result<(), &'static str> {
+        let mut game = Game::new();
+        for (id, name, team) in [
+            (1, "Red1", Team::Red),
+            (2, "Red2", Team::Red),
+            (3, "Blue1", Team::Blue),
+            (4, "Spec", Team::Spectator),
+        ] {
+            game.new_player(id);
+            game.rename_player(id, name.to_string())?;
+            game.change_team(id, team)?;
+            game.player_joined(id)?;
+        }
+
+        Action::Kill(1, 3, 10).parse(&mut game)?;
+        Action::Kill(3, 1, 10).parse(&mut game)?;
+        Action::Kill(1, 2, 10).parse(&mut game)?;
+        Action::Kill(WORLD, 3, 22).parse(&mut game)?;
+
+        assert_eq!(game.player_list, vec!["Red1", "Red2", "Blue1"]);
+        assert_eq!(
+            game.teams.get(&Team::Red),
+            Some(&TeamStats {
+                players: vec!["Red1".to_string(), "Red2".to_string()],
+                kills: 1,
+                team_kills: 1,
+            })
+        );
+        assert_eq!(
+            game.teams.get(&Team::Blue),
+            Some(&TeamStats {
+                players: vec!["Blue1".to_string()],
+                kills: 1,
+                team_kills: 0,
+            })
+        );
+        assert_eq!(game.kill_score.get("Red1"), Some(&2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_team_history() -> Result<(), &'static str> {
+        let mut game = Game::new();
+        game.new_player(1);
+        game.change_team(1, Team::Spectator)?;
+        game.change_team(1, Team::Red)?;
+        game.change_team(1, Team::Red)?;
+        game.change_team(1, Team::Blue)?;
+
+        assert_eq!(game.players[0].team, Team::Blue);
+        assert_eq!(
+            game.players[0].team_history,
+            vec![Team::Spectator, Team::Red, Team::Blue]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_scoring_rules() -> Result<(), &'static str> {
+        let mut game = Game::with_scoring(ScoringRules {
+            suicide: -1,
+            team_kill: -1,
+            first_blood: 2,
+            world_death: 0,
+            ..ScoringRules::default()
+        });
+        for (id, name, team) in [
+            (1, "Red1", Team::Red),
+            (2, "Red2", Team::Red),
+            (3, "Blue1", Team::Blue),
+        ] {
+            game.new_player(id);
+            game.rename_player(id, name.to_string())?;
+            game.change_team(id, team)?;
+            game.player_joined(id)?;
+        }
+
+        Action::Kill(WORLD, 3, 22).parse(&mut game)?;
+        Action::Kill(3, 1, 10).parse(&mut game)?;
+        Action::Kill(1, 2, 10).parse(&mut game)?;
+        Action::Kill(2, 2, 7).parse(&mut game)?;
+        Action::Kill(3, 2, 10).parse(&mut game)?;
+
+        assert_eq!(
+            game.first_blood.map(|f| f.killer),
+            Some("Blue1".to_string())
+        );
+        assert_eq!(game.kill_score.get("Blue1"), Some(&4));
+        assert_eq!(game.kill_score.get("Red1"), Some(&-1));
+        assert_eq!(game.kill_score.get("Red2"), Some(&-1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_team_kill_is_not_first_blood() -> Result<(), &'static str> {
+        let mut game = Game::with_scoring(ScoringRules {
+            team_kill: -1,
+            first_blood: 1,
+            ..ScoringRules::default()
+        });
+        for (id, name, team) in [
+            (1, "A", Team::Red),
+            (2, "B", Team::Red),
+            (3, "C", Team::Blue),
+        ] {
+            game.new_player(id);
+            game.rename_player(id, name.to_string())?;
+            game.change_team(id, team)?;
+            game.player_joined(id)?;
+        }
+
+        game.clock = 10;
+        Action::Kill(1, 2, 10).parse(&mut game)?;
+        assert_eq!(game.first_blood, None);
+        assert_eq!(game.kill_score.get("A"), Some(&-1));
+
+        game.clock = 20;
+        Action::Kill(3, 1, 10).parse(&mut game)?;
+        assert_eq!(
+            game.first_blood,
+            Some(FirstBlood {
+                killer: "C".to_string(),
+                victim: "A".to_string(),
+                time: 20,
+            })
+        );
+        assert_eq!(game.kill_score.get("C"), Some(&2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaks() -> Result<(), &'static str> {
+        let mut game = Game::new();
+        for (id, name) in [(1, "A"), (2, "B"), (3, "C")] {
+            game.new_player(id);
+            game.rename_player(id, name.to_string())?;
+            game.player_joined(id)?;
+        }
+
+        for (time, killer, killed) in [
+            (10, 1, 2),
+            (11, 1, 3),
+            (30, 1, 2),
+            (31, 3, 1),
+            (32, WORLD, 3),
+        ] {
+            game.clock = time;
+            Action::Kill(killer, killed, 10).parse(&mut game)?;
+        }
+
+        assert_eq!(
+            game.first_blood,
+            Some(FirstBlood {
+                killer: "A".to_string(),
+                victim: "B".to_string(),
+                time: 10,
+            })
+        );
+        assert_eq!(game.streaks["A"].longest_streak, 3);
+        assert_eq!(game.streaks["A"].double_kills, 1);
+        assert_eq!(game.streaks["C"].current_streak, 0);
+        assert_eq!(
+            game.ended_streaks,
+            vec![EndedStreak {
+                player: "A".to_string(),
+                kills: 3,
+                ended_by: "C".to_string(),
+                time: 31,
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_play_time() -> Result<(), &'static str> {
+        let mut game = Game::new();
+        for (id, name) in [(1, "A"), (2, "B")] {
+            game.new_player(id);
+            game.rename_player(id, name.to_string())?;
+        }
+
+        game.clock = 0;
+        game.player_joined(1)?;
+        game.clock = 60;
+        game.player_joined(2)?;
+        game.clock = 90;
+        Action::Kill(2, 1, 10).parse(&mut game)?;
+        game.clock = 120;
+        game.player_left(2)?;
+        game.clock = 180;
+        game.shutdown();
+
+        assert_eq!(
+            game.play_time["A"],
+            PlayTime {
+                seconds: 180,
+                kills: 0,
+                deaths: 1,
+            }
+        );
+        assert_eq!(game.play_time["B"].seconds, 60);
+        assert_eq!(game.play_time["B"].kills_per_minute(), 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_client_left() -> Result<(), &'static str> {
+        let mut game = Game::new();
+        game.new_player(1);
+        game.rename_player(1, "A".to_string())?;
+        game.player_joined(1)?;
+        game.clock = 30;
+        Action::ClientDisconnect(3).parse(&mut game)?;
+        game.clock = 60;
+        game.shutdown();
+
+        assert_eq!(game.player_list, vec!["A"]);
+        assert_eq!(game.play_time.len(), 1);
+        assert_eq!(game.play_time["A"].seconds, 60);
+        Ok(())
+    }
+
+    #[test]
+    fn test_winner_and_duration() -> Result<(), &'static str> {
+        let mut game = Game::new();
+        assert_eq!(game.winner(), None);
+        for (id, name) in [(1, "A"), (2, "B"), (3, "C")] {
+            game.new_player(id);
+            game.rename_player(id, name.to_string())?;
+            game.player_joined(id)?;
+        }
+        game.started_at = 30;
+        Action::Kill(2, 1, 10).parse(&mut game)?;
+        Action::Kill(1, 3, 10).parse(&mut game)?;
+        Action::Kill(3, 2, 10).parse(&mut game)?;
+        Action::Kill(2, 3, 10).parse(&mut game)?;
+        game.clock = 90;
+        game.shutdown();
+
+        assert_eq!(game.winner(), Some("B".to_string()));
+        assert_eq!(game.duration(), 60);
+
+        game.change_team(1, Team::Red)?;
+        game.change_team(2, Team::Blue)?;
+        Action::Kill(1, 2, 10).parse(&mut game)?;
+        assert_eq!(game.winner(), Some("red".to_string()));
+        Ok(())
+    }
 }
@@ -1,6 +1,15 @@
+#[allow(clippy::module_inception)]
 pub mod parser;
 pub mod player;
 pub mod game;
 pub mod actions;
+pub mod awards;
+pub mod collector;
+pub mod ranking;
+pub mod scoring;
+pub mod split;
+pub mod streaks;
+pub mod validate;
+pub mod writer;
 
-pub use parser::parse;
+pub use parser::{line_type, parse, parse_events, parse_parallel, parse_with};
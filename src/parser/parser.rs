@@ -1,14 +1,20 @@
+use super::collector::Collectors;
 use super::game::Game;
-use crate::parser::actions::Action;
+use super::scoring::ScoringRules;
+use crate::parser::actions::{Action, Event};
+use std::sync::Mutex;
+use std::thread;
 
 /// parses a vector of strings into a vector of actions that can be grouped and parsed
 ///
 /// parse will first parse the string vector into a plain Actions vector, and then group them into
-/// games and finally proceed to parse the games into a vector of Game structs;
+/// games and finally proceed to parse the games into a vector of Game structs; kills are scored
+/// according to the given [ScoringRules]
 ///
 /// # Example
 /// ```
-/// use parser::parse;
+/// use q3a_log_parser::parser::parse;
+/// use q3a_log_parser::parser::scoring::ScoringRules;
 /// let input = vec![
 /// "  0:00 ------------------------------------------------------------".to_string(),
 /// "  0:00 InitGame: ".to_string(),
@@ -21,96 +27,304 @@ use crate::parser::actions::Action;
 /// "  0:00 ------------------------------------------------------------".to_string(),
 /// ];
 ///
-/// let games = parse(input).unwrap();
-/// ````
+/// let games = parse(input, &ScoringRules::default()).unwrap();
+/// assert_eq!(games[0].total_kills, 1);
+/// assert_eq!(games[0].kill_score["Isgalamido"], 1);
+/// ```
 ///
 /// # Panics
 ///
 /// Panics if any of the lines are not in the expected format
 ///
-pub fn parse(buf: Vec<String>) -> Result<Vec<Game>, &'static str> {
-    let actions = parse_into_actions(buf);
+pub fn parse(buf: Vec<String>, scoring: &ScoringRules) -> Result<Vec<Game>, &'static str> {
+    parse_with(buf, scoring, &Collectors::builtin())
+}
 
-    group_by_game(actions)
-        .iter()
-        .map(|game| Action::parse_game(game.to_vec()))
+/// same as [parse], running the given collectors on each game instead of only the built-in ones
+pub fn parse_with(
+    buf: Vec<String>,
+    scoring: &ScoringRules,
+    collectors: &Collectors,
+) -> Result<Vec<Game>, &'static str> {
+    parse_events(buf)
+        .into_iter()
+        .map(|game| Action::parse_game(game, scoring, collectors))
         .collect()
 }
 
-fn parse_into_actions(buf: Vec<String>) -> Vec<Action> {
-    let mut actions: Vec<Action> = Vec::new();
-    for line in buf {
-        let parts = line.trim().split(' ').collect::<Vec<&str>>();
-        if parts.len() >= 2 {
-            match parts[1] {
-                "InitGame:" => {
-                    actions.push(Action::InitGame);
-                }
-                "ShutdownGame:" => {
-                    actions.push(Action::ShutdownGame);
-                }
-                "Kill:" => {
-                    if parts.len() < 5 {
-                        panic!("wrong number of parts on kill command");
+/// same as [parse_with], parsing and building the games on `jobs` threads
+///
+/// the log is cut where [parse] would start a new game: right before each `InitGame` and right
+/// after each `ShutdownGame`. the pieces are handed to the threads as they become free and the
+/// games are put back in the order they were played, so the result is the same [parse_with]
+/// gives. `jobs` of 0 or 1 parses on the calling thread
+///
+/// only the parsing is spread over the threads: the lines are all in memory before the log is
+/// cut, so this takes as much memory as [parse_with] does
+pub fn parse_parallel(
+    buf: Vec<String>,
+    scoring: &ScoringRules,
+    collectors: &Collectors,
+    jobs: usize,
+) -> Result<Vec<Game>, &'static str> {
+    if jobs <= 1 {
+        return parse_with(buf, scoring, collectors);
+    }
+
+    let pieces = split_at_games(buf);
+    let last = pieces.len().saturating_sub(1);
+    let queue = Mutex::new(pieces.into_iter().enumerate());
+    let mut built: Vec<(usize, Result<Game, &'static str>)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                let collectors = collectors.clone();
+                let queue = &queue;
+                scope.spawn(move || {
+                    let mut built = Vec::new();
+                    loop {
+                        let next = queue.lock().unwrap().next();
+                        let (index, (offset, lines)) = match next {
+                            Some(next) => next,
+                            None => break,
+                        };
+                        // a game cut short is closed by the next InitGame, on the next piece
+                        let grouped = group_events(parse_into_actions(lines), index != last);
+                        for mut events in grouped {
+                            events.iter_mut().for_each(|event| event.line += offset);
+                            built.push((index, Action::parse_game(events, scoring, &collectors)));
+                        }
                     }
-                    let killer = parts[2].parse::<u32>().expect("could not parse killer id");
-                    let killed = parts[3].parse::<u32>().expect("could not parse killed id");
-                    let means_of_death = parts[4]
-                        .trim_matches(':')
-                        .parse::<u32>()
-                        .expect("could not parse means of death id");
-                    actions.push(Action::Kill(killer, killed, means_of_death));
+                    built
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                // malformed lines panic, like they do when parsing on a single thread
+                worker
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    });
+
+    built.sort_by_key(|(index, _)| *index);
+    built.into_iter().map(|(_, game)| game).collect()
+}
+
+/// cuts the lines where [group_by_game] starts a new game, along with the number of lines
+/// before each piece
+fn split_at_games(buf: Vec<String>) -> Vec<(usize, Vec<String>)> {
+    let mut pieces = Vec::new();
+    let mut piece = Vec::new();
+    let mut offset = 0;
+    for (i, line) in buf.into_iter().enumerate() {
+        let keyword = tokenize(&line)
+            .filter(|tokens| parse_timestamp(tokens.time).is_some())
+            .map(|tokens| tokens.keyword);
+        if keyword == Some("InitGame:") && !piece.is_empty() {
+            pieces.push((offset, std::mem::take(&mut piece)));
+            offset = i;
+        }
+        let shutdown = keyword == Some("ShutdownGame:");
+        piece.push(line);
+        if shutdown {
+            pieces.push((offset, std::mem::take(&mut piece)));
+            offset = i + 1;
+        }
+    }
+    if !piece.is_empty() {
+        pieces.push((offset, piece));
+    }
+    pieces
+}
+
+/// parses the lines into events grouped by game, without building the games
+pub fn parse_events(buf: Vec<String>) -> Vec<Vec<Event>> {
+    group_by_game(parse_into_actions(buf))
+}
+
+pub(super) fn parse_into_actions(buf: Vec<String>) -> Vec<Event> {
+    let mut events: Vec<Event> = Vec::new();
+    for (i, line) in buf.iter().enumerate() {
+        let tokens = match tokenize(line) {
+            Some(tokens) => tokens,
+            None => continue,
+        };
+        // lines without a clock aren't written by the server, whatever they look like
+        let time = match parse_timestamp(tokens.time) {
+            Some(time) => time,
+            None => continue,
+        };
+        // malformed lines of the types the parser reads are formatting errors
+        let action = parse_action(tokens.keyword, tokens.payload)
+            .unwrap_or_else(|e| panic!("{} on line {}", e, i + 1));
+        if let Some(action) = action {
+            events.push(Event {
+                time,
+                line: i + 1,
+                action,
+            });
+        }
+    }
+
+    events
+}
+
+/// the action a line stands for, from its keyword and payload. lines the parser doesn't care
+/// about give `None`, and lines it does care about that it can't read give an error
+pub(super) fn parse_action(keyword: &str, payload: &str) -> Result<Option<Action>, &'static str> {
+    // most payloads start with a client id
+    let (word, rest) = payload.split_once(' ').unwrap_or((payload, ""));
+    let action = match keyword {
+        "InitGame:" => Action::InitGame(payload.to_string()),
+        "ShutdownGame:" => Action::ShutdownGame,
+        "Kill:" => {
+            let mut ids = payload.split(' ');
+            let (killer, killed, means_of_death) = match (ids.next(), ids.next(), ids.next()) {
+                (Some(killer), Some(killed), Some(means_of_death)) => {
+                    (killer, killed, means_of_death)
                 }
-                "ClientConnect:" => match parts[2].parse::<u32>() {
-                    Ok(client) => actions.push(Action::ClientConnect(client)),
-                    Err(_) => (),
-                },
-                "ClientBegin:" => match parts[2].parse::<u32>() {
-                    Ok(client) => actions.push(Action::ClientBegin(client)),
-                    Err(_) => (),
-                },
-                "ClientUserinfoChanged:" => match parts[2].parse::<u32>() {
-                    Ok(client) => {
-                        actions.push(Action::ClientUserinfoChanged(client, parts[3..].join(" ")))
-                    }
-                    Err(e) => {
-                        panic!("Could not parse client id: {}", e);
-                    }
-                },
-                "ClientDisconnect:" => match parts[2].parse::<u32>() {
-                    Ok(client) => actions.push(Action::ClientDisconnect(client)),
-                    Err(e) => panic!("Could not parse client id: {}", e),
-                },
-                _ => (),
+                _ => return Err("wrong number of parts on kill command"),
+            };
+            let killer = killer.parse::<u32>().or(Err("could not parse killer id"))?;
+            let killed = killed.parse::<u32>().or(Err("could not parse killed id"))?;
+            let means_of_death = means_of_death
+                .trim_matches(':')
+                .parse::<u32>()
+                .or(Err("could not parse means of death id"))?;
+            Action::Kill(killer, killed, means_of_death)
+        }
+        "ClientConnect:" => return Ok(word.parse::<u32>().ok().map(Action::ClientConnect)),
+        "ClientBegin:" => return Ok(word.parse::<u32>().ok().map(Action::ClientBegin)),
+        "ClientUserinfoChanged:" => {
+            let client = word.parse::<u32>().or(Err("could not parse client id"))?;
+            Action::ClientUserinfoChanged(client, rest.to_string())
+        }
+        "ClientDisconnect:" => {
+            Action::ClientDisconnect(word.parse::<u32>().or(Err("could not parse client id"))?)
+        }
+        "Item:" => {
+            if !payload.contains(' ') {
+                return Err("wrong number of parts on item command");
             }
+            let client = word.parse::<u32>().or(Err("could not parse client id"))?;
+            Action::Item(client, rest.to_string())
+        }
+        // chat without a sender isn't read, see [validate](super::validate::validate)
+        "say:" => {
+            return Ok(payload
+                .split_once(": ")
+                .map(|(player, message)| Action::Say(player.to_string(), message.to_string())))
         }
+        "sayteam:" => {
+            return Ok(payload.split_once(": ").map(|(player, message)| {
+                Action::SayTeam(player.to_string(), message.to_string())
+            }))
+        }
+        // `<sender> to <recipient>: <message>`
+        "tell:" => {
+            let tell = payload.split_once(" to ").and_then(|(player, rest)| {
+                let (recipient, message) = rest.split_once(": ")?;
+                Some(Action::Tell(
+                    player.to_string(),
+                    recipient.to_string(),
+                    message.to_string(),
+                ))
+            });
+            return Ok(tell);
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(action))
+}
+
+/// a log line cut into its parts, borrowing from the line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct Tokens<'a> {
+    /// the `minutes:seconds` prefix, without its padding
+    pub time: &'a str,
+    /// the word right after the timestamp, such as `Kill:` or the dashes of a separator
+    pub keyword: &'a str,
+    /// everything after the space that follows the keyword, exactly as it was written
+    pub payload: &'a str,
+}
+
+/// cuts a line into its timestamp, keyword and payload without copying it. lines with nothing
+/// after the timestamp give `None`
+pub(super) fn tokenize(line: &str) -> Option<Tokens<'_>> {
+    let (time, rest) = line.trim_start().split_once(' ')?;
+    let (keyword, payload) = rest.split_once(' ').unwrap_or((rest, ""));
+    Some(Tokens {
+        time,
+        keyword,
+        payload,
+    })
+}
+
+/// parses the `minutes:seconds` prefix of a log line into seconds
+pub(super) fn parse_timestamp(time: &str) -> Option<u32> {
+    let (minutes, seconds) = time.split_once(':')?;
+    Some(minutes.parse::<u32>().ok()? * 60 + seconds.parse::<u32>().ok()?)
+}
+
+/// the type of a log line, the word right after the timestamp up to its colon
+///
+/// the dashed lines the server writes around each game are reported as `separator`. lines with
+/// nothing after the timestamp have no type
+///
+/// # Example
+/// ```
+/// use q3a_log_parser::parser::line_type;
+/// assert_eq!(line_type("  0:25 Kill: 2 3 7: Isgalamido killed Zeh by MOD_ROCKET"), Some("Kill"));
+/// assert_eq!(line_type("  0:00 ----------------------------"), Some("separator"));
+/// assert_eq!(line_type("  2:50 red:8  blue:6"), Some("red"));
+/// assert_eq!(line_type("  0:00"), None);
+/// ```
+pub fn line_type(line: &str) -> Option<&str> {
+    let word = tokenize(line)?.keyword;
+    if word.is_empty() {
+        None
+    } else if word.chars().all(|c| c == '-') {
+        Some("separator")
+    } else {
+        word.split(':').next()
     }
+}
 
-    actions
+fn group_by_game(events: Vec<Event>) -> Vec<Vec<Event>> {
+    group_events(events, false)
 }
 
-fn group_by_game(actions: Vec<Action>) -> Vec<Vec<Action>> {
-    let mut grouped_actions: Vec<Vec<Action>> = Vec::new();
-    let mut game: Vec<Action> = Vec::new();
-    for action in actions {
-        match action {
-            Action::InitGame => {
-                if game.len() > 0 {
-                    grouped_actions.push(std::mem::replace(&mut game, Vec::new()));
+/// groups the events like [group_by_game]. the events after the last `ShutdownGame` are only
+/// kept as a game when `keep_unfinished` is set, for pieces of a log that are followed by an
+/// `InitGame`
+fn group_events(events: Vec<Event>, keep_unfinished: bool) -> Vec<Vec<Event>> {
+    let mut grouped_events: Vec<Vec<Event>> = Vec::new();
+    let mut game: Vec<Event> = Vec::new();
+    for event in events {
+        match event.action {
+            Action::InitGame(_) => {
+                if !game.is_empty() {
+                    grouped_events.push(std::mem::take(&mut game));
                 }
-                game.push(action);
+                game.push(event);
             }
             Action::ShutdownGame => {
-                game.push(action);
-                grouped_actions.push(std::mem::replace(&mut game, Vec::new()));
+                game.push(event);
+                grouped_events.push(std::mem::take(&mut game));
             }
             _ => {
-                game.push(action);
+                game.push(event);
             }
         }
     }
+    if keep_unfinished && !game.is_empty() {
+        grouped_events.push(game);
+    }
 
-    grouped_actions
+    grouped_events
 }
 
 #[cfg(test)]
@@ -136,7 +350,7 @@ mod tests {
             "  0:00 ------------------------------------------------------------".to_string(),
         ];
         let expected = vec![
-            Action::InitGame,
+            Action::InitGame(String::new()),
             Action::ClientConnect(2),
             Action::ClientUserinfoChanged(2, "n\\Isgalamido\\t\\0\\model\\uriel/zael\\hmodel\\uriel/zael\\g_redteam\\g_redteam\\g_blue".to_string()),
             Action::ClientConnect(3),
@@ -148,13 +362,152 @@ mod tests {
             Action::Kill(2, 3, 7),
             Action::ShutdownGame,
         ];
-        assert_eq!(parse_into_actions(input), expected);
+        let actions: Vec<Action> = parse_into_actions(input)
+            .into_iter()
+            .map(|e| e.action)
+            .collect();
+        assert_eq!(actions, expected);
+    }
+
+    #[test]
+    fn test_parse_timestamps() {
+        let input = vec![
+            "  0:00 InitGame: ".to_string(),
+            "  1:08 Kill: 3 2 6: Isgalamido killed Mocinha by MOD_ROCKET".to_string(),
+            "981:39 ShutdownGame:".to_string(),
+        ];
+        let times: Vec<(u32, usize)> = parse_into_actions(input)
+            .iter()
+            .map(|e| (e.time, e.line))
+            .collect();
+        assert_eq!(times, vec![(0, 1), (68, 2), (58899, 3)]);
+        assert_eq!(parse_timestamp("12"), None);
+    }
+
+    #[test]
+    fn test_skip_garbled_timestamps() {
+        let input = vec![
+            "  0:00 InitGame: ".to_string(),
+            "xx:01 ClientConnect: 2".to_string(),
+            "  0:x2 Kill: 2 3".to_string(),
+            "  0:03 ClientConnect: 3".to_string(),
+        ];
+        let events: Vec<(usize, Action)> = parse_into_actions(input)
+            .into_iter()
+            .map(|e| (e.line, e.action))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (1, Action::InitGame(String::new())),
+                (4, Action::ClientConnect(3))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_items_and_chat() {
+        let input = vec![
+            "  0:00 InitGame: \\sv_hostname\\Code Miner Server\\mapname\\q3dm17".to_string(),
+            "  0:00 Item: 2 weapon_rocketlauncher".to_string(),
+            "981:21 say: Oootsimo: team red: now".to_string(),
+            "  0:00 say: no separator".to_string(),
+            "  0:01 sayteam: Zeh: cover me".to_string(),
+            "  0:02 tell: Zeh to Dono da Bola: psst: here".to_string(),
+            "  0:03 tell: Zeh: no recipient".to_string(),
+        ];
+        let expected = vec![
+            Action::InitGame("\\sv_hostname\\Code Miner Server\\mapname\\q3dm17".to_string()),
+            Action::Item(2, "weapon_rocketlauncher".to_string()),
+            Action::Say("Oootsimo".to_string(), "team red: now".to_string()),
+            Action::SayTeam("Zeh".to_string(), "cover me".to_string()),
+            Action::Tell(
+                "Zeh".to_string(),
+                "Dono da Bola".to_string(),
+                "psst: here".to_string(),
+            ),
+        ];
+        let actions: Vec<Action> = parse_into_actions(input)
+            .into_iter()
+            .map(|e| e.action)
+            .collect();
+        assert_eq!(actions, expected);
+    }
+
+    #[test]
+    fn test_items_of_unknown_clients_are_skipped() {
+        let input = vec![
+            "  0:00 InitGame: \\mapname\\q3dm17".to_string(),
+            "  0:01 ClientConnect: 2".to_string(),
+            "  0:01 ClientUserinfoChanged: 2 n\\Isgalamido\\t\\0".to_string(),
+            "  0:01 ClientBegin: 2".to_string(),
+            "  0:05 Item: 2 weapon_railgun".to_string(),
+            "  0:06 Item: 4 weapon_rocketlauncher".to_string(),
+            "  0:09 ShutdownGame:".to_string(),
+        ];
+        let games = parse(input, &ScoringRules::default()).unwrap();
+        assert_eq!(games.len(), 1);
+        let items: Vec<(&str, &str)> = games[0]
+            .items
+            .iter()
+            .map(|pickup| (pickup.player.as_str(), pickup.item.as_str()))
+            .collect();
+        assert_eq!(items, vec![("Isgalamido", "weapon_railgun")]);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("  0:25 say: Dono  da Bola:  gg "),
+            Some(Tokens {
+                time: "0:25",
+                keyword: "say:",
+                payload: "Dono  da Bola:  gg ",
+            })
+        );
+        assert_eq!(
+            tokenize("981:39 ShutdownGame:"),
+            Some(Tokens {
+                time: "981:39",
+                keyword: "ShutdownGame:",
+                payload: "",
+            })
+        );
+        assert_eq!(tokenize("  0:00"), None);
+    }
+
+    #[test]
+    fn test_payload_is_kept_as_written() {
+        let input = vec![
+            "  0:00 ClientUserinfoChanged: 2 n\\Dono  da Bola \\t\\0".to_string(),
+            "  0:01 say: Dono  da Bola :  not  again ".to_string(),
+        ];
+        let expected = vec![
+            Action::ClientUserinfoChanged(2, "n\\Dono  da Bola \\t\\0".to_string()),
+            Action::Say("Dono  da Bola ".to_string(), " not  again ".to_string()),
+        ];
+        let actions: Vec<Action> = parse_into_actions(input)
+            .into_iter()
+            .map(|e| e.action)
+            .collect();
+        assert_eq!(actions, expected);
+    }
+
+    fn at_zero(actions: Vec<Action>) -> Vec<Event> {
+        actions
+            .into_iter()
+            .map(|action| Event {
+                time: 0,
+                line: 0,
+                action,
+            })
+            .collect()
     }
 
     #[test]
     fn test_group() {
         let given = vec![
-            Action::InitGame,
+            Action::InitGame(String::new()),
             Action::ClientConnect(2),
             Action::ClientUserinfoChanged(2, "n\\Isgalamido\\t\\0\\model\\uriel/zael\\hmodel\\uriel/zael\\g_redteam\\g_redteam\\g_blue".to_string()),
             Action::ClientConnect(3),
@@ -168,7 +521,7 @@ mod tests {
         ];
         let expected = vec![
             vec![
-                Action::InitGame,
+                Action::InitGame(String::new()),
                 Action::ClientConnect(2),
                 Action::ClientUserinfoChanged(2, "n\\Isgalamido\\t\\0\\model\\uriel/zael\\hmodel\\uriel/zael\\g_redteam\\g_redteam\\g_blue".to_string()),
                 Action::ClientConnect(3),
@@ -184,11 +537,15 @@ mod tests {
             ]
         ];
 
-        assert_eq!(group_by_game(given), expected);
+        let expected: Vec<Vec<Event>> = expected.into_iter().map(at_zero).collect();
+        assert_eq!(group_by_game(at_zero(given)), expected);
     }
 
-    use super::super::game::KillMode;
-    use super::super::player::Player;
+    use super::super::awards::Awards;
+    use super::super::game::{KillMode, KillRecord, Source};
+    use super::super::player::{PlayTime, Player, Session, Team};
+    use super::super::streaks::{FirstBlood, StreakStats};
+    use std::collections::BTreeMap;
 
     #[test]
     fn test_parse() {
@@ -209,6 +566,50 @@ mod tests {
         means_of_death.insert(KillMode::ModRocketSplash, 1);
         let mut kill_score = std::collections::HashMap::new();
         kill_score.insert("Isgalamido".to_string(), 1);
+        let mut streaks = std::collections::HashMap::new();
+        streaks.insert(
+            "Isgalamido".to_string(),
+            StreakStats {
+                longest_streak: 1,
+                current_streak: 1,
+                chain: 1,
+                last_kill: Some(0),
+                ..StreakStats::default()
+            },
+        );
+        let mut awards = std::collections::HashMap::new();
+        awards.insert(
+            "Isgalamido".to_string(),
+            Awards {
+                perfect: true,
+                last_frag: Some(0),
+                ..Awards::default()
+            },
+        );
+        awards.insert(
+            "Dono da Bola".to_string(),
+            Awards {
+                died: true,
+                ..Awards::default()
+            },
+        );
+        let mut play_time = std::collections::HashMap::new();
+        play_time.insert(
+            "Isgalamido".to_string(),
+            PlayTime {
+                seconds: 0,
+                kills: 1,
+                deaths: 0,
+            },
+        );
+        play_time.insert(
+            "Dono da Bola".to_string(),
+            PlayTime {
+                seconds: 0,
+                kills: 0,
+                deaths: 1,
+            },
+        );
 
         let expected = vec![Game {
             total_kills: 1,
@@ -217,18 +618,113 @@ mod tests {
                     id: 2,
                     name: "Isgalamido".to_string(),
                     joined: true,
+                    team: Team::Free,
+                    team_history: vec![Team::Free],
+                    sessions: vec![Session {
+                        start: 0,
+                        end: Some(0),
+                    }],
                 },
                 Player {
                     id: 3,
                     name: "Dono da Bola".to_string(),
                     joined: true,
+                    team: Team::Free,
+                    team_history: vec![Team::Free],
+                    sessions: vec![Session {
+                        start: 0,
+                        end: Some(0),
+                    }],
                 },
-            ],
+            ]
+            .into(),
             player_list: vec!["Isgalamido".to_string(), "Dono da Bola".to_string()],
             kill_score,
             means_of_death,
+            teams: std::collections::HashMap::new(),
+            first_blood: Some(FirstBlood {
+                killer: "Isgalamido".to_string(),
+                victim: "Dono da Bola".to_string(),
+                time: 0,
+            }),
+            streaks,
+            ended_streaks: vec![],
+            awards,
+            play_time,
+            source: Some(Source {
+                file: None,
+                first_line: 2,
+                last_line: 10,
+                fingerprint: None,
+            }),
+            stats: BTreeMap::new(),
+            kill_feed: vec![KillRecord {
+                time: 0,
+                killer: "Isgalamido".to_string(),
+                victim: "Dono da Bola".to_string(),
+                means_of_death: KillMode::ModRocketSplash,
+            }],
+            settings: std::collections::HashMap::new(),
+            items: vec![],
+            chat: vec![],
+            scoring: ScoringRules::default(),
+            clock: 0,
+            started_at: 0,
+            ended_at: 0,
+            collectors: Collectors::builtin(),
         }];
 
-        assert_eq!(parse(given).unwrap(), expected);
+        assert_eq!(parse(given, &ScoringRules::default()).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_spectators_are_not_listed() {
+        let content = std::fs::read_to_string("input").unwrap();
+        let lines: Vec<String> = content.lines().map(String::from).collect();
+        let games = parse(lines, &ScoringRules::default()).unwrap();
+
+        // the other clients on these games are on the spectator team
+        assert_eq!(games[14].player_list, vec!["Assasinu Credi"]);
+        assert_eq!(games[15].player_list, vec!["Oootsimo", "Isgalamido"]);
+        assert!(games[14].players.iter().any(|p| p.team == Team::Spectator));
+    }
+
+    #[test]
+    fn test_parse_parallel() {
+        let content = std::fs::read_to_string("input").unwrap();
+        let lines: Vec<String> = content.lines().map(String::from).collect();
+        let scoring = ScoringRules::default();
+        let collectors = Collectors::builtin();
+
+        // the log has a game without ShutdownGame, and gets cut short on the last one
+        let cut_short = &lines[..lines.len() - 30];
+        for lines in [&lines[..], cut_short] {
+            let serial = parse_with(lines.to_vec(), &scoring, &collectors).unwrap();
+            for jobs in [2, 3, 8] {
+                let parallel = parse_parallel(lines.to_vec(), &scoring, &collectors, jobs);
+                assert_eq!(parallel.unwrap(), serial);
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_at_games() {
+        let lines: Vec<String> = "  0:00 Kill: 1022 2 22: <world> killed Zeh by MOD_TRIGGER_HURT
+  0:01 ShutdownGame:
+  0:01 ------------------------------------------------------------
+  0:00 InitGame: \\mapname\\q3dm17
+  0:05 ClientConnect: 2
+  0:00 InitGame: \\mapname\\q3dm6
+xx:00 InitGame: \\mapname\\q3dm6
+  0:10 ShutdownGame:"
+            .lines()
+            .map(String::from)
+            .collect();
+
+        let pieces: Vec<(usize, usize)> = split_at_games(lines)
+            .iter()
+            .map(|(offset, piece)| (*offset, piece.len()))
+            .collect();
+        assert_eq!(pieces, vec![(0, 2), (2, 1), (3, 2), (5, 3)]);
     }
 }
@@ -1,10 +1,56 @@
-use serde::Serialize;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
 
-#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+use super::game::TeamStats;
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Team {
+    #[default]
+    Free,
+    Red,
+    Blue,
+    Spectator,
+}
+
+impl Team {
+    /// maps the `t` userinfo value (TEAM_FREE, TEAM_RED, TEAM_BLUE, TEAM_SPECTATOR) into a Team
+    pub fn from_u32(n: u32) -> Option<Team> {
+        match n {
+            0 => Some(Team::Free),
+            1 => Some(Team::Red),
+            2 => Some(Team::Blue),
+            3 => Some(Team::Spectator),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Team::Free => "free",
+            Team::Red => "red",
+            Team::Blue => "blue",
+            Team::Spectator => "spectator",
+        }
+    }
+
+    /// whether the team is one of the sides of a team based game (TDM/CTF)
+    pub fn is_playing_side(&self) -> bool {
+        matches!(self, Team::Red | Team::Blue)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Player {
     pub id: u32,
     pub name: String,
     pub joined: bool,
+    pub team: Team,
+    /// every team the player has been assigned to, in order
+    pub team_history: Vec<Team>,
+    /// periods the player was in the match, from `ClientBegin` until they left or the game ended
+    pub sessions: Vec<Session>,
 }
 
 impl Player {
@@ -13,6 +59,327 @@ impl Player {
             id,
             name: String::new(),
             joined: false,
+            team: Team::Free,
+            team_history: Vec::new(),
+            sessions: Vec::new(),
+        }
+    }
+
+    pub fn change_team(&mut self, team: Team) {
+        if self.team_history.last() != Some(&team) {
+            self.team_history.push(team);
+        }
+        self.team = team;
+    }
+
+    /// opens a new session unless the player is already playing one
+    pub fn begin_session(&mut self, time: u32) {
+        if !self.in_session() {
+            self.sessions.push(Session {
+                start: time,
+                end: None,
+            });
+        }
+    }
+
+    /// closes the open session, returning how long it lasted
+    pub fn end_session(&mut self, time: u32) -> Option<u32> {
+        match self.sessions.last_mut() {
+            Some(session) if session.end.is_none() => {
+                session.end = Some(time);
+                Some(time.saturating_sub(session.start))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn in_session(&self) -> bool {
+        matches!(self.sessions.last(), Some(session) if session.end.is_none())
+    }
+}
+
+/// the players of a game, looked up by the client slot they're on
+///
+/// every `ClientConnect` adds a player, but a slot keeps pointing at the first player that
+/// connected on it, so a client reconnecting goes on with the name, team and sessions it had.
+/// the players shown on the game's lists are tracked by their position, so the lists can be
+/// updated for a single player instead of being built again from every player
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Players {
+    players: Vec<Player>,
+    /// position of the player on each client slot
+    slots: HashMap<u32, usize>,
+    /// positions of the players on the game's player list, in the order they connected
+    listed: Vec<usize>,
+    /// positions of the players on each team's list, in the order they connected
+    teams: HashMap<Team, Vec<usize>>,
+}
+
+impl Players {
+    pub fn new() -> Players {
+        Players::default()
+    }
+
+    /// adds a player for a client connecting on the slot
+    pub fn connect(&mut self, id: u32) {
+        self.slots.entry(id).or_insert(self.players.len());
+        self.players.push(Player::new(id));
+    }
+
+    pub fn slot(&self, id: u32) -> Option<&Player> {
+        self.slots.get(&id).map(|&index| &self.players[index])
+    }
+
+    /// the player on the slot, to be changed. [Players::update_lists] has to be called after
+    /// changing anything shown on the lists
+    pub fn slot_mut(&mut self, id: u32) -> Option<&mut Player> {
+        let index = *self.slots.get(&id)?;
+        Some(&mut self.players[index])
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Player> {
+        self.players.iter_mut()
+    }
+
+    /// puts the name of the player on the slot on the player list and on its team's list, or
+    /// takes it out of them if the player isn't playing. `player_list` may also hold an empty
+    /// name for each player that connected since it was last updated, and those are dropped
+    pub fn update_lists(
+        &mut self,
+        id: u32,
+        player_list: &mut Vec<String>,
+        teams: &mut HashMap<Team, TeamStats>,
+    ) {
+        player_list.truncate(self.listed.len());
+        let index = match self.slots.get(&id) {
+            Some(&index) => index,
+            None => return,
+        };
+        let player = &self.players[index];
+        let playing = player.joined && player.team != Team::Spectator;
+
+        let name = playing.then_some(player.name.as_str());
+        place(&mut self.listed, player_list, index, name);
+
+        if playing && player.team.is_playing_side() {
+            self.teams.entry(player.team).or_default();
+            teams.entry(player.team).or_default();
+        }
+        for (team, listed) in self.teams.iter_mut() {
+            let name = name.filter(|_| player.team == *team);
+            place(
+                listed,
+                &mut teams.entry(*team).or_default().players,
+                index,
+                name,
+            );
+        }
+        self.teams.retain(|_, listed| !listed.is_empty());
+    }
+}
+
+/// keeps `names` matching the players at the `listed` positions, adding, renaming or removing
+/// the player at `index`
+fn place(listed: &mut Vec<usize>, names: &mut Vec<String>, index: usize, name: Option<&str>) {
+    match (listed.binary_search(&index), name) {
+        (Ok(position), Some(name)) => {
+            if names[position] != name {
+                names[position] = name.to_string();
+            }
+        }
+        (Ok(position), None) => {
+            listed.remove(position);
+            names.remove(position);
         }
+        (Err(position), Some(name)) => {
+            listed.insert(position, index);
+            names.insert(position, name.to_string());
+        }
+        (Err(_), None) => {}
+    }
+}
+
+impl From<Vec<Player>> for Players {
+    fn from(players: Vec<Player>) -> Players {
+        let mut slots = HashMap::new();
+        let mut listed = Vec::new();
+        let mut teams: HashMap<Team, Vec<usize>> = HashMap::new();
+        for (index, player) in players.iter().enumerate() {
+            slots.entry(player.id).or_insert(index);
+            if player.joined && player.team != Team::Spectator {
+                listed.push(index);
+                if player.team.is_playing_side() {
+                    teams.entry(player.team).or_default().push(index);
+                }
+            }
+        }
+        Players {
+            players,
+            slots,
+            listed,
+            teams,
+        }
+    }
+}
+
+impl std::ops::Deref for Players {
+    type Target = [Player];
+
+    fn deref(&self) -> &[Player] {
+        &self.players
+    }
+}
+
+impl<'a> IntoIterator for &'a Players {
+    type Item = &'a Player;
+    type IntoIter = std::slice::Iter<'a, Player>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.players.iter()
+    }
+}
+
+impl PartialEq<Vec<Player>> for Players {
+    fn eq(&self, other: &Vec<Player>) -> bool {
+        &self.players == other
+    }
+}
+
+// serialized as a plain list, the lookups are only needed while the game is being built
+impl Serialize for Players {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.players.serialize(serializer)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Session {
+    pub start: u32,
+    pub end: Option<u32>,
+}
+
+/// time a player spent in game and what they did with it
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct PlayTime {
+    pub seconds: u32,
+    pub kills: u32,
+    pub deaths: u32,
+}
+
+impl PlayTime {
+    pub fn kills_per_minute(&self) -> f64 {
+        per_minute(self.kills, self.seconds)
+    }
+
+    pub fn deaths_per_minute(&self) -> f64 {
+        per_minute(self.deaths, self.seconds)
+    }
+
+    pub fn merge(&mut self, other: &PlayTime) {
+        self.seconds += other.seconds;
+        self.kills += other.kills;
+        self.deaths += other.deaths;
+    }
+}
+
+fn per_minute(count: u32, seconds: u32) -> f64 {
+    if seconds == 0 {
+        return 0.0;
+    }
+    // rounded to two decimal places to keep reports readable
+    (count as f64 * 6000.0 / seconds as f64).round() / 100.0
+}
+
+// the rates are derived from the counters, so they're only computed when serializing
+impl Serialize for PlayTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("PlayTime", 5)?;
+        state.serialize_field("seconds", &self.seconds)?;
+        state.serialize_field("kills", &self.kills)?;
+        state.serialize_field("deaths", &self.deaths)?;
+        state.serialize_field("kills_per_minute", &self.kills_per_minute())?;
+        state.serialize_field("deaths_per_minute", &self.deaths_per_minute())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions() {
+        let mut player = Player::new(1);
+        player.begin_session(10);
+        player.begin_session(20);
+        assert_eq!(player.end_session(70), Some(60));
+        assert_eq!(player.end_session(80), None);
+        player.begin_session(100);
+
+        assert!(player.in_session());
+        assert_eq!(player.sessions.len(), 2);
+    }
+
+    #[test]
+    fn test_players_lists() {
+        let mut players = Players::new();
+        let mut player_list = Vec::new();
+        let mut teams = HashMap::new();
+        for id in [2, 3, 4] {
+            players.connect(id);
+            player_list.push(String::new());
+        }
+        for (id, name, team) in [
+            (4, "C", Team::Blue),
+            (2, "A", Team::Red),
+            (3, "B", Team::Red),
+        ] {
+            let player = players.slot_mut(id).unwrap();
+            player.name = name.to_string();
+            player.joined = true;
+            player.change_team(team);
+            players.update_lists(id, &mut player_list, &mut teams);
+        }
+        assert_eq!(player_list, vec!["A", "B", "C"]);
+        assert_eq!(teams[&Team::Red].players, vec!["A", "B"]);
+
+        // a reconnect on a taken slot keeps pointing at the player that had it
+        players.connect(2);
+        player_list.push(String::new());
+        assert_eq!(players.len(), 4);
+        assert_eq!(players.slot(2).unwrap().name, "A");
+
+        let player = players.slot_mut(2).unwrap();
+        player.name = "A2".to_string();
+        player.change_team(Team::Blue);
+        players.update_lists(2, &mut player_list, &mut teams);
+        assert_eq!(player_list, vec!["A2", "B", "C"]);
+        assert_eq!(teams[&Team::Red].players, vec!["B"]);
+        assert_eq!(teams[&Team::Blue].players, vec!["A2", "C"]);
+
+        players.slot_mut(3).unwrap().change_team(Team::Spectator);
+        players.update_lists(3, &mut player_list, &mut teams);
+        assert_eq!(player_list, vec!["A2", "C"]);
+        assert!(teams[&Team::Red].players.is_empty());
+
+        // the same lists are found from the players alone
+        let rebuilt = Players::from(players.to_vec());
+        assert_eq!(rebuilt, players);
+    }
+
+    #[test]
+    fn test_play_time_rates() {
+        let time = PlayTime {
+            seconds: 90,
+            kills: 4,
+            deaths: 1,
+        };
+        assert_eq!(time.kills_per_minute(), 2.67);
+        assert_eq!(time.deaths_per_minute(), 0.67);
+        assert_eq!(PlayTime::default().kills_per_minute(), 0.0);
+        assert_eq!(
+            serde_json::to_string(&time).unwrap(),
+            r#"{"seconds":90,"kills":4,"deaths":1,"kills_per_minute":2.67,"deaths_per_minute":0.67}"#
+        );
     }
 }
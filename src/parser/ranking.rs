@@ -0,0 +1,121 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::game::Game;
+use super::player::PlayTime;
+
+/// a player's statistics aggregated over every game they took part in
+#[derive(Clone, Debug, Default, Serialize, PartialEq, Eq)]
+pub struct PlayerRanking {
+    pub name: String,
+    pub games: u32,
+    pub kills: i32,
+    pub longest_streak: u32,
+    pub double_kills: u32,
+    pub triple_kills: u32,
+    pub multi_kills: u32,
+    pub first_bloods: u32,
+    pub excellent: u32,
+    pub impressive: u32,
+    pub gauntlet: u32,
+    /// games finished with at least one frag and no deaths
+    pub perfect: u32,
+    pub play_time: PlayTime,
+}
+
+/// builds the player ranking across all given games, sorted by kill score
+pub fn rank(games: &[Game]) -> Vec<PlayerRanking> {
+    let mut players: HashMap<String, PlayerRanking> = HashMap::new();
+    for game in games {
+        for name in &game.player_list {
+            ranking_entry(&mut players, name).games += 1;
+        }
+        for (name, score) in &game.kill_score {
+            ranking_entry(&mut players, name).kills += score;
+        }
+        for (name, streaks) in &game.streaks {
+            let player = ranking_entry(&mut players, name);
+            player.longest_streak = player.longest_streak.max(streaks.longest_streak);
+            player.double_kills += streaks.double_kills;
+            player.triple_kills += streaks.triple_kills;
+            player.multi_kills += streaks.multi_kills;
+        }
+        for (name, awards) in &game.awards {
+            let player = ranking_entry(&mut players, name);
+            player.excellent += awards.excellent;
+            player.impressive += awards.impressive;
+            player.gauntlet += awards.gauntlet;
+            player.perfect += awards.perfect as u32;
+        }
+        for (name, play_time) in &game.play_time {
+            ranking_entry(&mut players, name).play_time.merge(play_time);
+        }
+        if let Some(first_blood) = &game.first_blood {
+            ranking_entry(&mut players, &first_blood.killer).first_bloods += 1;
+        }
+    }
+
+    let mut ranking: Vec<PlayerRanking> = players.into_values().collect();
+    ranking.sort_by(|a, b| b.kills.cmp(&a.kills).then_with(|| a.name.cmp(&b.name)));
+    ranking
+}
+
+fn ranking_entry<'a>(
+    players: &'a mut HashMap<String, PlayerRanking>,
+    name: &str,
+) -> &'a mut PlayerRanking {
+    players
+        .entry(name.to_string())
+        .or_insert_with(|| PlayerRanking {
+            name: name.to_string(),
+            ..PlayerRanking::default()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::actions::Action;
+
+    #[test]
+    fn test_rank() -> Result<(), &'static str> {
+        let mut games = vec![Game::new(), Game::new()];
+        for game in games.iter_mut() {
+            for (id, name) in [(1, "A"), (2, "B")] {
+                game.new_player(id);
+                game.rename_player(id, name.to_string())?;
+                game.player_joined(id)?;
+            }
+        }
+        Action::Kill(1, 2, 10).parse(&mut games[0])?;
+        Action::Kill(1, 2, 10).parse(&mut games[0])?;
+        Action::Kill(2, 1, 10).parse(&mut games[1])?;
+
+        let ranking = rank(&games);
+        assert_eq!(ranking.len(), 2);
+        assert_eq!(
+            ranking[0],
+            PlayerRanking {
+                name: "A".to_string(),
+                games: 2,
+                kills: 2,
+                longest_streak: 2,
+                double_kills: 1,
+                first_bloods: 1,
+                excellent: 1,
+                impressive: 1,
+                perfect: 1,
+                play_time: PlayTime {
+                    seconds: 0,
+                    kills: 2,
+                    deaths: 1,
+                },
+                ..PlayerRanking::default()
+            }
+        );
+        assert_eq!(ranking[1].name, "B");
+        assert_eq!(ranking[1].first_bloods, 1);
+        assert_eq!(ranking[1].play_time.deaths, 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+/// points given to a player for each kind of kill event
+///
+/// the default preset follows the challenge rules: +1 for each kill, -1 when `<world>` kills the
+/// player and nothing for suicides. rules can also be loaded from a TOML or JSON file; any missing
+/// field falls back to the default value.
+///
+/// # Example
+/// ```toml
+/// suicide = -1
+/// team_kill = -1
+/// first_blood = 1
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoringRules {
+    /// killing a player from another team (or anyone on free for all games)
+    pub kill: i32,
+    /// being killed by `<world>`
+    pub world_death: i32,
+    /// killing yourself
+    pub suicide: i32,
+    /// killing a player from your own team on team based games
+    pub team_kill: i32,
+    /// bonus added to the first player to kill another player in the game
+    pub first_blood: i32,
+    /// seconds between kills for them to be chained into a multi kill
+    pub multi_kill_window: u32,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringRules {
+            kill: 1,
+            world_death: -1,
+            suicide: 0,
+            team_kill: 1,
+            first_blood: 0,
+            multi_kill_window: 3,
+        }
+    }
+}
+
+impl ScoringRules {
+    /// returns one of the built-in rule sets by name
+    ///
+    /// # Example
+    /// ```
+    /// use q3a_log_parser::ScoringRules;
+    ///
+    /// let rules = ScoringRules::preset("quake3").unwrap();
+    /// assert_eq!(rules.suicide, -1);
+    /// assert!(ScoringRules::preset("unknown").is_none());
+    /// ```
+    pub fn preset(name: &str) -> Option<ScoringRules> {
+        match name {
+            "challenge" => Some(ScoringRules::default()),
+            // mirrors the real server: suicides and team kills cost a frag
+            "quake3" => Some(ScoringRules {
+                suicide: -1,
+                team_kill: -1,
+                ..ScoringRules::default()
+            }),
+            "no-world-penalty" => Some(ScoringRules {
+                world_death: 0,
+                ..ScoringRules::default()
+            }),
+            _ => None,
+        }
+    }
+
+    /// parses a rule file. files ending in `.toml` are read as TOML, everything else as JSON
+    pub fn from_file(path: &str) -> Result<ScoringRules, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return Err(format!("could not read scoring file: {}", e)),
+        };
+
+        if path.ends_with(".toml") {
+            toml::from_str(&content).map_err(|e| format!("invalid scoring file: {}", e))
+        } else {
+            serde_json::from_str(&content).map_err(|e| format!("invalid scoring file: {}", e))
+        }
+    }
+
+    /// resolves either a preset name or a path to a rule file
+    pub fn load(source: &str) -> Result<ScoringRules, String> {
+        match ScoringRules::preset(source) {
+            Some(rules) => Ok(rules),
+            None => ScoringRules::from_file(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        for name in ["challenge", "quake3", "no-world-penalty"] {
+            assert!(ScoringRules::preset(name).is_some());
+        }
+        assert_eq!(
+            ScoringRules::preset("challenge"),
+            Some(ScoringRules::default())
+        );
+        assert_eq!(ScoringRules::preset("unknown"), None);
+    }
+
+    #[test]
+    fn test_load_files() {
+        std::fs::write("test_scoring.toml", "suicide = -1\nfirst_blood = 2\n").unwrap();
+        std::fs::write("test_scoring.json", r#"{"world_death": 0}"#).unwrap();
+
+        let toml = ScoringRules::load("test_scoring.toml");
+        let json = ScoringRules::load("test_scoring.json");
+
+        std::fs::remove_file("test_scoring.toml").unwrap();
+        std::fs::remove_file("test_scoring.json").unwrap();
+
+        assert_eq!(
+            toml,
+            Ok(ScoringRules {
+                suicide: -1,
+                first_blood: 2,
+                ..ScoringRules::default()
+            })
+        );
+        assert_eq!(
+            json,
+            Ok(ScoringRules {
+                world_death: 0,
+                ..ScoringRules::default()
+            })
+        );
+        assert!(ScoringRules::load("does_not_exist.json").is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use super::actions::{parse_info, Action};
+use super::parser::{line_type, parse_events};
+
+/// the raw lines of a single game, as they were written on the log
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameLines<'a> {
+    pub index: usize,
+    /// map from the `InitGame` settings. `None` for a game the log doesn't show the start of
+    pub map: Option<String>,
+    /// number of the first line of the game on the log, starting at 1
+    pub first_line: usize,
+    pub lines: &'a [String],
+}
+
+impl GameLines<'_> {
+    /// `game_<index>_<map>_<first line>.log`, with the index padded to `width` digits so the
+    /// files sort in the order they were played
+    pub fn file_name(&self, width: usize) -> String {
+        let map: String = self
+            .map
+            .as_deref()
+            .unwrap_or("unknown")
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+                _ => '_',
+            })
+            .collect();
+        format!(
+            "game_{:0width$}_{}_{}.log",
+            self.index,
+            map,
+            self.first_line,
+            width = width
+        )
+    }
+}
+
+/// splits a log into the raw lines of each game, keeping every line untouched
+///
+/// games are found the same way [parse](super::parse) finds them. each game starts on the
+/// separator right before its `InitGame` and runs until the next game starts, so its
+/// `ShutdownGame`, the closing separator and any line the parser ignores are kept. lines before
+/// the first game go along with it, so concatenating the games gives back the whole log
+///
+/// # Example
+/// ```
+/// use q3a_log_parser::parser::split::split;
+///
+/// let log: Vec<String> = "  0:00 ------------------------------------------------------------
+///   0:00 InitGame: \\mapname\\q3dm17
+///  20:37 ShutdownGame:
+///  20:37 ------------------------------------------------------------
+///   0:00 ------------------------------------------------------------
+///   0:00 InitGame: \\mapname\\q3dm6
+///   1:47 ShutdownGame:"
+///     .lines()
+///     .map(String::from)
+///     .collect();
+///
+/// let games = split(&log);
+/// assert_eq!(games.len(), 2);
+/// assert_eq!(games[0].lines.len(), 4);
+/// assert_eq!(games[1].first_line, 5);
+/// assert_eq!(games[1].file_name(2), "game_01_q3dm6_5.log");
+/// ```
+pub fn split(lines: &[String]) -> Vec<GameLines<'_>> {
+    let grouped = parse_events(lines.to_vec());
+
+    let mut starts = Vec::with_capacity(grouped.len());
+    let mut previous_end = 0;
+    for events in &grouped {
+        let (first, last) = match (events.first(), events.last()) {
+            (Some(first), Some(last)) => (first.line, last.line),
+            _ => continue,
+        };
+        let start = if starts.is_empty() {
+            1
+        } else if first - 1 > previous_end && line_type(&lines[first - 2]) == Some("separator") {
+            first - 1
+        } else {
+            first
+        };
+        let map = match &events[0].action {
+            Action::InitGame(info) => parse_info(info).remove("mapname"),
+            _ => None,
+        };
+        starts.push((start, map));
+        previous_end = last;
+    }
+
+    let mut games = Vec::with_capacity(starts.len());
+    for (index, (first_line, map)) in starts.iter().cloned().enumerate() {
+        let end = match starts.get(index + 1) {
+            Some((next, _)) => next - 1,
+            None => lines.len(),
+        };
+        games.push(GameLines {
+            index,
+            map,
+            first_line,
+            lines: &lines[first_line - 1..end],
+        });
+    }
+    games
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let content = "  0:00 ------------------------------------------------------------
+  0:00 InitGame: \\mapname\\q3dm17
+  0:01 ClientConnect: 2
+ 15:00 Exit: Timelimit hit.
+ 20:37 ShutdownGame:
+ 20:37 ------------------------------------------------------------
+  0:00 ------------------------------------------------------------
+  0:00 InitGame: \\mapname\\q3dm6
+  0:01 ClientConnect: 2
+ 26  0:00 ------------------------------------------------------------
+  0:00 InitGame: \\g_gametype\\4\\mapname\\q3/ctf1
+  0:10 red:8  blue:6
+  1:47 ShutdownGame:
+  1:47 ------------------------------------------------------------";
+        let lines: Vec<String> = content.lines().map(String::from).collect();
+
+        let games = split(&lines);
+        assert_eq!(games.len(), 3);
+        assert_eq!(
+            games
+                .iter()
+                .map(|game| (game.first_line, game.lines.len()))
+                .collect::<Vec<_>>(),
+            vec![(1, 6), (7, 4), (11, 4)]
+        );
+        assert_eq!(games[0].lines[3], " 15:00 Exit: Timelimit hit.");
+        // the truncated line is kept with the game that was cut short
+        assert_eq!(games[1].lines[3], lines[9]);
+        assert_eq!(games[2].file_name(1), "game_2_q3_ctf1_11.log");
+
+        // nothing is lost or reordered
+        assert_eq!(
+            games.iter().flat_map(|g| g.lines).collect::<Vec<_>>(),
+            lines.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_split_started_mid_game() {
+        let lines: Vec<String> = "  5:00 Kill: 1022 2 22: <world> killed Zeh by MOD_TRIGGER_HURT
+  6:00 ShutdownGame:
+  6:00 ------------------------------------------------------------"
+            .lines()
+            .map(String::from)
+            .collect();
+
+        let games = split(&lines);
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].map, None);
+        assert_eq!(games[0].lines.len(), 3);
+        assert_eq!(games[0].file_name(3), "game_000_unknown_1.log");
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+/// streaks shorter than this are not reported when they end
+pub const MIN_REPORTED_STREAK: u32 = 3;
+
+/// kill streak and multi kill counters for a single player in a game
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StreakStats {
+    /// most kills made without dying
+    pub longest_streak: u32,
+    pub double_kills: u32,
+    pub triple_kills: u32,
+    /// chains of four or more kills
+    pub multi_kills: u32,
+    #[serde(skip)]
+    pub current_streak: u32,
+    /// kills made so far in the current multi kill window
+    #[serde(skip)]
+    pub chain: u32,
+    #[serde(skip)]
+    pub last_kill: Option<u32>,
+}
+
+impl StreakStats {
+    /// registers a kill made at `time`. kills made up to `window` seconds apart from each other are
+    /// chained into a multi kill; a chain is only counted once, under its final size
+    pub fn add_kill(&mut self, time: u32, window: u32) {
+        self.current_streak += 1;
+        self.longest_streak = self.longest_streak.max(self.current_streak);
+
+        self.chain = match self.last_kill {
+            Some(last) if time.saturating_sub(last) <= window => self.chain + 1,
+            _ => 1,
+        };
+        self.last_kill = Some(time);
+
+        match self.chain {
+            2 => self.double_kills += 1,
+            3 => {
+                self.double_kills -= 1;
+                self.triple_kills += 1;
+            }
+            4 => {
+                self.triple_kills -= 1;
+                self.multi_kills += 1;
+            }
+            _ => (),
+        }
+    }
+
+    /// resets the streak after the player dies, returning the streak that was ended
+    pub fn end_streak(&mut self) -> u32 {
+        self.chain = 0;
+        self.last_kill = None;
+        std::mem::take(&mut self.current_streak)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FirstBlood {
+    pub killer: String,
+    pub victim: String,
+    pub time: u32,
+}
+
+/// a streak of at least [MIN_REPORTED_STREAK] kills and who put an end to it
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EndedStreak {
+    pub player: String,
+    pub kills: u32,
+    pub ended_by: String,
+    pub time: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streaks() {
+        let mut stats = StreakStats::default();
+        stats.add_kill(10, 3);
+        stats.add_kill(20, 3);
+        stats.add_kill(30, 3);
+        assert_eq!(stats.end_streak(), 3);
+        stats.add_kill(40, 3);
+
+        assert_eq!(stats.longest_streak, 3);
+        assert_eq!(stats.current_streak, 1);
+        assert_eq!(stats.double_kills, 0);
+    }
+
+    #[test]
+    fn test_multi_kills() {
+        let mut stats = StreakStats::default();
+        // double kill
+        stats.add_kill(10, 3);
+        stats.add_kill(12, 3);
+        // triple kill
+        stats.add_kill(20, 3);
+        stats.add_kill(21, 3);
+        stats.add_kill(24, 3);
+        // five kills chained
+        for time in 40..45 {
+            stats.add_kill(time, 3);
+        }
+        // dying breaks the chain
+        stats.add_kill(50, 3);
+        stats.end_streak();
+        stats.add_kill(51, 3);
+
+        assert_eq!(stats.double_kills, 1);
+        assert_eq!(stats.triple_kills, 1);
+        assert_eq!(stats.multi_kills, 1);
+        assert_eq!(stats.longest_streak, 11);
+    }
+}
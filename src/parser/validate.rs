@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::actions::{Action, Event};
+use super::game::WORLD;
+use super::parser::{line_type, parse_action, parse_timestamp, tokenize};
+
+/// line types written by ioq3 that the parser knows about, even if it doesn't turn them into
+/// actions
+const KNOWN_LINE_TYPES: &[&str] = &[
+    "InitGame",
+    "ShutdownGame",
+    "ClientConnect",
+    "ClientUserinfoChanged",
+    "ClientBegin",
+    "ClientDisconnect",
+    "Kill",
+    "Item",
+    "Exit",
+    "score",
+    "red",
+    "say",
+    "sayteam",
+    "tell",
+    "separator",
+];
+
+/// chat line types, which the parser skips when it can't tell who sent them
+const CHAT_LINE_TYPES: &[&str] = &["say", "sayteam", "tell"];
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// `ClientBegin`, `ClientUserinfoChanged`, `ClientDisconnect`, `Item` or `Kill` mentioning a
+    /// client that never connected to the game
+    UnknownClient,
+    /// a kill involving a client that had already disconnected
+    DisconnectedClient,
+    /// `InitGame` while the previous game was never shut down
+    DuplicateInitGame,
+    /// a timestamp earlier than the previous one on the same game
+    TimeWentBackwards,
+    /// a kill after the game announced its `Exit`
+    KillAfterExit,
+    /// a line type the parser doesn't know about
+    UnknownLineType,
+    /// a line with a timestamp that isn't `minutes:seconds`, a `Kill`, `Item` or client line the
+    /// parser can't read, or a chat line without a sender
+    MalformedLine,
+}
+
+/// a structural problem found on a log, pointing at the line it was found on
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct Anomaly {
+    pub line: usize,
+    pub kind: AnomalyKind,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Client {
+    Connected,
+    Disconnected,
+}
+
+/// state of the game the lines being validated belong to
+#[derive(Default)]
+struct Validator {
+    anomalies: Vec<Anomaly>,
+    /// line the current game started on, if it wasn't shut down yet
+    started_at: Option<usize>,
+    exited: bool,
+    last_time: Option<u32>,
+    clients: HashMap<u32, Client>,
+}
+
+/// checks the lines of a log for structural anomalies
+///
+/// unlike [parse](super::parse) the whole log is always checked, and each problem is reported
+/// along with its line number instead of stopping on the first one, malformed lines included
+///
+/// # Example
+/// ```
+/// use q3a_log_parser::parser::validate::{validate, AnomalyKind};
+///
+/// let lines: Vec<String> = vec![
+///     "  0:00 InitGame: \\mapname\\q3dm17".to_string(),
+///     "  0:01 ClientBegin: 2".to_string(),
+///     "  0:02 ShutdownGame:".to_string(),
+/// ];
+/// let anomalies = validate(&lines);
+/// assert_eq!(anomalies.len(), 1);
+/// assert_eq!(anomalies[0].line, 2);
+/// assert_eq!(anomalies[0].kind, AnomalyKind::UnknownClient);
+/// ```
+pub fn validate(lines: &[String]) -> Vec<Anomaly> {
+    let mut validator = Validator::default();
+
+    for (i, line) in lines.iter().enumerate() {
+        let number = i + 1;
+        let (tokens, kind) = match (tokenize(line), line_type(line)) {
+            (Some(tokens), Some(kind)) => (tokens, kind),
+            _ => continue,
+        };
+        if !KNOWN_LINE_TYPES.contains(&kind) {
+            validator.report(
+                number,
+                AnomalyKind::UnknownLineType,
+                format!("unknown line type {}", kind),
+            );
+            continue;
+        }
+        let time = match parse_timestamp(tokens.time) {
+            Some(time) => time,
+            None => {
+                validator.report(
+                    number,
+                    AnomalyKind::MalformedLine,
+                    format!("malformed timestamp {}", tokens.time),
+                );
+                continue;
+            }
+        };
+
+        match parse_action(tokens.keyword, tokens.payload) {
+            Ok(Some(action)) => validator.check(&Event {
+                time,
+                line: number,
+                action,
+            }),
+            Ok(None) if CHAT_LINE_TYPES.contains(&kind) => {
+                validator.report(
+                    number,
+                    AnomalyKind::MalformedLine,
+                    format!("{} line without a sender", kind),
+                );
+            }
+            Ok(None) => {
+                validator.check_time(number, time);
+                if kind == "Exit" {
+                    validator.exited = true;
+                }
+            }
+            Err(e) => validator.report(number, AnomalyKind::MalformedLine, e.to_string()),
+        }
+    }
+
+    validator.anomalies
+}
+
+impl Validator {
+    fn report(&mut self, line: usize, kind: AnomalyKind, message: String) {
+        self.anomalies.push(Anomaly {
+            line,
+            kind,
+            message,
+        });
+    }
+
+    /// separators written between games carry the time of either game, so only the lines of a
+    /// running game are checked
+    fn check_time(&mut self, line: usize, time: u32) {
+        if self.started_at.is_none() {
+            return;
+        }
+        if let Some(last) = self.last_time {
+            if time < last {
+                self.report(
+                    line,
+                    AnomalyKind::TimeWentBackwards,
+                    format!("time went back from {}s to {}s", last, time),
+                );
+            }
+        }
+        self.last_time = Some(time);
+    }
+
+    /// reports an anomaly if the client never connected, returning whether it did
+    fn check_connected(&mut self, line: usize, id: u32, action: &str) -> bool {
+        if self.clients.contains_key(&id) {
+            return true;
+        }
+        self.report(
+            line,
+            AnomalyKind::UnknownClient,
+            format!("{} for client {} that never connected", action, id),
+        );
+        false
+    }
+
+    fn check(&mut self, event: &Event) {
+        let line = event.line;
+        if let Action::InitGame(_) = event.action {
+            if let Some(started_at) = self.started_at {
+                self.report(
+                    line,
+                    AnomalyKind::DuplicateInitGame,
+                    format!(
+                        "InitGame while the game started on line {} was never shut down",
+                        started_at
+                    ),
+                );
+            }
+            self.started_at = Some(line);
+            self.exited = false;
+            self.last_time = None;
+            self.clients.clear();
+        }
+        self.check_time(line, event.time);
+
+        match &event.action {
+            Action::InitGame(_) | Action::Say(..) | Action::SayTeam(..) | Action::Tell(..) => {}
+            Action::ShutdownGame => {
+                self.started_at = None;
+                self.last_time = None;
+            }
+            Action::ClientConnect(id) => {
+                self.clients.insert(*id, Client::Connected);
+            }
+            Action::ClientBegin(id) => {
+                self.check_connected(line, *id, "ClientBegin");
+            }
+            Action::ClientUserinfoChanged(id, _) => {
+                self.check_connected(line, *id, "ClientUserinfoChanged");
+            }
+            Action::Item(id, _) => {
+                self.check_connected(line, *id, "Item");
+            }
+            Action::ClientDisconnect(id) => {
+                if self.check_connected(line, *id, "ClientDisconnect") {
+                    self.clients.insert(*id, Client::Disconnected);
+                }
+            }
+            Action::Kill(killer, killed, _) => {
+                if self.exited {
+                    self.report(
+                        line,
+                        AnomalyKind::KillAfterExit,
+                        "kill after the game exited".to_string(),
+                    );
+                }
+                let involved = [(*killer, "killer"), (*killed, "victim")];
+                for (id, role) in involved {
+                    if id == WORLD || !self.check_connected(line, id, "Kill") {
+                        continue;
+                    }
+                    if self.clients[&id] == Client::Disconnected {
+                        self.report(
+                            line,
+                            AnomalyKind::DisconnectedClient,
+                            format!("{} {} had already disconnected", role, id),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(content: &str) -> Vec<String> {
+        content.lines().map(String::from).collect()
+    }
+
+    fn kinds(anomalies: &[Anomaly]) -> Vec<(usize, AnomalyKind)> {
+        anomalies.iter().map(|a| (a.line, a.kind)).collect()
+    }
+
+    #[test]
+    fn test_valid_log() {
+        let log = lines(
+            "  0:00 ------------------------------------------------------------
+  0:00 InitGame: \\mapname\\q3dm17
+  0:01 ClientConnect: 2
+  0:01 ClientUserinfoChanged: 2 n\\Isgalamido\\t\\0
+  0:01 ClientBegin: 2
+  0:02 ClientConnect: 3
+  0:02 ClientUserinfoChanged: 3 n\\Zeh\\t\\0
+  0:02 ClientBegin: 3
+  0:05 Item: 2 weapon_railgun
+  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN
+  0:12 say: Zeh: gg
+  0:15 ClientDisconnect: 3
+  0:20 Exit: Fraglimit hit.
+  0:20 score: 1  ping: 4  client: 2 Isgalamido
+  0:20 ShutdownGame:
+  0:20 ------------------------------------------------------------
+  0:00 ------------------------------------------------------------
+  0:00 InitGame: \\mapname\\q3dm6
+  0:01 ClientConnect: 3
+  0:01 ClientBegin: 3
+  0:02 ShutdownGame:",
+        );
+        assert_eq!(validate(&log), vec![]);
+    }
+
+    #[test]
+    fn test_anomalies() {
+        let log = lines(
+            "  0:00 InitGame: \\mapname\\q3dm17
+  0:01 ClientUserinfoChanged: 4 n\\Ghost\\t\\0
+  0:01 ClientConnect: 2
+  0:01 ClientConnect: 3
+  0:05 ClientDisconnect: 3
+  0:04 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN
+  0:06 Exit: Timelimit hit.
+  0:07 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT
+  0:08 Weather: raining
+  0:00 InitGame: \\mapname\\q3dm6
+  0:01 ClientBegin: 2
+  0:02 ShutdownGame:",
+        );
+        let anomalies = validate(&log);
+        assert_eq!(
+            kinds(&anomalies),
+            vec![
+                (2, AnomalyKind::UnknownClient),
+                (6, AnomalyKind::TimeWentBackwards),
+                (6, AnomalyKind::DisconnectedClient),
+                (8, AnomalyKind::KillAfterExit),
+                (9, AnomalyKind::UnknownLineType),
+                (10, AnomalyKind::DuplicateInitGame),
+                (11, AnomalyKind::UnknownClient),
+            ]
+        );
+        assert_eq!(anomalies[2].message, "victim 3 had already disconnected");
+        assert_eq!(
+            anomalies[5].message,
+            "InitGame while the game started on line 1 was never shut down"
+        );
+
+        let json = serde_json::to_value(&anomalies[4]).unwrap();
+        assert_eq!(json["kind"], "unknown_line_type");
+        assert_eq!(json["line"], 9);
+    }
+
+    #[test]
+    fn test_malformed_lines() {
+        let log = lines(
+            "  0:00 InitGame: \\mapname\\q3dm17
+  0:01 ClientConnect: 2
+  0:01 ClientUserinfoChanged: two n\\Isgalamido\\t\\0
+  0:01 Kill: 2 3
+  0:02 Kill: 2 x 10: Isgalamido killed Zeh by MOD_RAILGUN
+xx:03 ClientConnect: 3
+  0:04 ClientBegin: 2
+  0:04 say: nobody said this
+  0:04 tell: Isgalamido: to whom?
+  0:05 ShutdownGame:",
+        );
+        let anomalies = validate(&log);
+        assert_eq!(
+            kinds(&anomalies),
+            vec![
+                (3, AnomalyKind::MalformedLine),
+                (4, AnomalyKind::MalformedLine),
+                (5, AnomalyKind::MalformedLine),
+                (6, AnomalyKind::MalformedLine),
+                (8, AnomalyKind::MalformedLine),
+                (9, AnomalyKind::MalformedLine),
+            ]
+        );
+        let messages: Vec<&str> = anomalies.iter().map(|a| a.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "could not parse client id",
+                "wrong number of parts on kill command",
+                "could not parse killed id",
+                "malformed timestamp xx:03",
+                "say line without a sender",
+                "tell line without a sender",
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&anomalies[0]).unwrap()["kind"],
+            "malformed_line"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use super::actions::{Action, Event};
+use super::game::{KillMode, WORLD};
+
+/// written by the server right before `InitGame` and right after `ShutdownGame`
+pub const SEPARATOR: &str = "------------------------------------------------------------";
+
+/// renders actions back into the lines ioq3 writes on its log, the inverse of the parser
+///
+/// kill lines mention the names of the players involved, so the writer keeps track of the names
+/// sent on `ClientUserinfoChanged` the same way a game does
+#[derive(Clone, Debug, Default)]
+pub struct LogWriter {
+    names: HashMap<u32, String>,
+}
+
+impl LogWriter {
+    pub fn new() -> LogWriter {
+        LogWriter::default()
+    }
+
+    /// the line the action is logged as at the given time, in seconds
+    ///
+    /// # Example
+    /// ```
+    /// use q3a_log_parser::parser::writer::LogWriter;
+    /// use q3a_log_parser::Action;
+    ///
+    /// let mut writer = LogWriter::new();
+    /// writer.line(0, &Action::ClientUserinfoChanged(2, "n\\Isgalamido\\t\\0".to_string()));
+    /// writer.line(0, &Action::ClientUserinfoChanged(3, "n\\Zeh\\t\\0".to_string()));
+    /// assert_eq!(
+    ///     writer.line(125, &Action::Kill(2, 3, 10)),
+    ///     "  2:05 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN"
+    /// );
+    /// ```
+    pub fn line(&mut self, time: u32, action: &Action) -> String {
+        let text = match action {
+            Action::InitGame(settings) => {
+                self.names.clear();
+                format!("InitGame: {}", settings)
+            }
+            Action::ShutdownGame => "ShutdownGame:".to_string(),
+            Action::ClientConnect(id) => format!("ClientConnect: {}", id),
+            Action::ClientBegin(id) => format!("ClientBegin: {}", id),
+            Action::ClientDisconnect(id) => format!("ClientDisconnect: {}", id),
+            Action::ClientUserinfoChanged(id, userinfo) => {
+                if let Some(name) = userinfo.split('\\').nth(1) {
+                    self.names.insert(*id, name.to_string());
+                }
+                format!("ClientUserinfoChanged: {} {}", id, userinfo)
+            }
+            Action::Item(id, item) => format!("Item: {} {}", id, item),
+            Action::Say(player, message) => format!("say: {}: {}", player, message),
+            Action::SayTeam(player, message) => format!("sayteam: {}: {}", player, message),
+            Action::Tell(player, recipient, message) => {
+                format!("tell: {} to {}: {}", player, recipient, message)
+            }
+            Action::Kill(killer, killed, means_of_death) => format!(
+                "Kill: {} {} {}: {} killed {} by {}",
+                killer,
+                killed,
+                means_of_death,
+                self.name(*killer),
+                self.name(*killed),
+                KillMode::from_u32(*means_of_death).as_str()
+            ),
+        };
+        format!("{} {}", timestamp(time), text)
+    }
+
+    fn name(&self, id: u32) -> &str {
+        if id == WORLD {
+            return "<world>";
+        }
+        self.names.get(&id).map(|name| name.as_str()).unwrap_or("")
+    }
+}
+
+/// formats seconds as the `minutes:seconds` prefix of a log line, with the minutes padded to 3
+/// characters like the server does
+pub fn timestamp(time: u32) -> String {
+    format!("{:>3}:{:02}", time / 60, time % 60)
+}
+
+/// renders a sequence of events into a log, with separators around each game like ioq3 writes
+/// them. parsing the result gives back the same actions at the same times
+pub fn write_log(events: &[Event]) -> Vec<String> {
+    let mut writer = LogWriter::new();
+    let mut lines = Vec::with_capacity(events.len());
+    for event in events {
+        if let Action::InitGame(_) = event.action {
+            lines.push(format!("{} {}", timestamp(event.time), SEPARATOR));
+        }
+        lines.push(writer.line(event.time, &event.action));
+        if let Action::ShutdownGame = event.action {
+            lines.push(format!("{} {}", timestamp(event.time), SEPARATOR));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::parse_into_actions;
+    use proptest::prelude::*;
+
+    fn timed(events: &[Event]) -> Vec<(u32, Action)> {
+        events
+            .iter()
+            .map(|event| (event.time, event.action.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_write_log() {
+        let events: Vec<Event> = vec![
+            Action::InitGame("\\sv_hostname\\Code Miner Server\\mapname\\q3dm17".to_string()),
+            Action::ClientConnect(2),
+            Action::ClientUserinfoChanged(2, "n\\Dono da Bola\\t\\0\\model\\sarge".to_string()),
+            Action::ClientBegin(2),
+            Action::Item(2, "weapon_rocketlauncher".to_string()),
+            Action::Kill(WORLD, 2, 22),
+            Action::Say("Dono da Bola".to_string(), "not again: lava".to_string()),
+            Action::SayTeam("Dono da Bola".to_string(), "on my way".to_string()),
+            Action::Tell(
+                "Dono da Bola".to_string(),
+                "Zeh".to_string(),
+                "rematch?".to_string(),
+            ),
+            Action::ClientDisconnect(2),
+            Action::ShutdownGame,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, action)| Event {
+            time: i as u32 * 30,
+            line: i + 1,
+            action,
+        })
+        .collect();
+
+        let lines = write_log(&events);
+        assert_eq!(
+            lines,
+            vec![
+                "  0:00 ------------------------------------------------------------",
+                "  0:00 InitGame: \\sv_hostname\\Code Miner Server\\mapname\\q3dm17",
+                "  0:30 ClientConnect: 2",
+                "  1:00 ClientUserinfoChanged: 2 n\\Dono da Bola\\t\\0\\model\\sarge",
+                "  1:30 ClientBegin: 2",
+                "  2:00 Item: 2 weapon_rocketlauncher",
+                "  2:30 Kill: 1022 2 22: <world> killed Dono da Bola by MOD_TRIGGER_HURT",
+                "  3:00 say: Dono da Bola: not again: lava",
+                "  3:30 sayteam: Dono da Bola: on my way",
+                "  4:00 tell: Dono da Bola to Zeh: rematch?",
+                "  4:30 ClientDisconnect: 2",
+                "  5:00 ShutdownGame:",
+                "  5:00 ------------------------------------------------------------",
+            ]
+        );
+        assert_eq!(timed(&parse_into_actions(lines)), timed(&events));
+    }
+
+    #[test]
+    fn test_rewrite_example_log() {
+        let example = std::fs::read_to_string("example").unwrap();
+        let events = parse_into_actions(example.lines().map(String::from).collect());
+
+        let rewritten = parse_into_actions(write_log(&events));
+        assert_eq!(timed(&rewritten), timed(&events));
+    }
+
+    /// text on a single line. payloads are kept as written, so any run of spaces is fine
+    fn text(chars: &'static str) -> impl Strategy<Value = String> {
+        prop::string::string_regex(&format!("[{} ]*", chars)).unwrap()
+    }
+
+    fn client() -> impl Strategy<Value = u32> {
+        prop_oneof![0..64u32, Just(WORLD)]
+    }
+
+    fn action() -> impl Strategy<Value = Action> {
+        prop_oneof![
+            prop::collection::vec((text("a-z_"), text("A-Za-z0-9_./")), 0..6).prop_map(
+                |settings| Action::InitGame(
+                    settings
+                        .iter()
+                        .map(|(key, value)| format!("\\{}\\{}", key, value))
+                        .collect()
+                )
+            ),
+            Just(Action::ShutdownGame),
+            (client(), client(), 0..32u32).prop_map(|(k, v, m)| Action::Kill(k, v, m)),
+            client().prop_map(Action::ClientConnect),
+            client().prop_map(Action::ClientBegin),
+            client().prop_map(Action::ClientDisconnect),
+            (client(), text("A-Za-z0-9_<>!.:"), 0..4u32).prop_map(|(id, name, team)| {
+                Action::ClientUserinfoChanged(id, format!("n\\{}\\t\\{}\\model\\sarge", name, team))
+            }),
+            (client(), "[a-z_]{1,20}").prop_map(|(id, item)| Action::Item(id, item)),
+            (text("A-Za-z0-9_<>!."), text("A-Za-z0-9_<>!.:?"))
+                .prop_map(|(player, message)| { Action::Say(player, message) }),
+            (text("A-Za-z0-9_<>!."), text("A-Za-z0-9_<>!.:?"))
+                .prop_map(|(player, message)| { Action::SayTeam(player, message) }),
+            // names without spaces, the first ` to ` ends the sender's name
+            (
+                "[A-Za-z0-9_<>!.]*",
+                "[A-Za-z0-9_<>!.]*",
+                text("A-Za-z0-9_<>!.:?")
+            )
+                .prop_map(|(player, recipient, message)| Action::Tell(player, recipient, message)),
+        ]
+    }
+
+    fn events() -> impl Strategy<Value = Vec<Event>> {
+        prop::collection::vec((0..100_000u32, action()), 0..64).prop_map(|actions| {
+            actions
+                .into_iter()
+                .enumerate()
+                .map(|(i, (time, action))| Event {
+                    time,
+                    line: i + 1,
+                    action,
+                })
+                .collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_write_parse(events in events()) {
+            let lines = write_log(&events);
+            let parsed = parse_into_actions(lines.clone());
+            prop_assert_eq!(timed(&parsed), timed(&events));
+
+            // and writing the parsed events again gives back the same log
+            prop_assert_eq!(write_log(&parsed), lines);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::parser::game::Game;
+
+/// a single CSV table, named after the file it's written to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table {
+    pub name: &'static str,
+    pub content: String,
+}
+
+/// renders the games into normalized tables: games, players, means_of_death and kills.
+/// every table references its game by the same index used on the JSON report
+pub fn tables(games: &[Game]) -> Vec<Table> {
+    let mut games_table = String::from("game,total_kills,players,first_blood\n");
+    let mut players = String::from("game,player,score,kills,deaths,play_time\n");
+    let mut means_of_death = String::from("game,means_of_death,count\n");
+    let mut kills = String::from("game,time,killer,victim,means_of_death\n");
+
+    for (i, game) in games.iter().enumerate() {
+        let first_blood = match &game.first_blood {
+            Some(first_blood) => first_blood.killer.as_str(),
+            None => "",
+        };
+        push_row(
+            &mut games_table,
+            &[
+                i.to_string(),
+                game.total_kills.to_string(),
+                game.player_list.len().to_string(),
+                first_blood.to_string(),
+            ],
+        );
+
+        for name in &game.player_list {
+            let play_time = game.play_time.get(name).cloned().unwrap_or_default();
+            push_row(
+                &mut players,
+                &[
+                    i.to_string(),
+                    name.clone(),
+                    game.kill_score.get(name).unwrap_or(&0).to_string(),
+                    play_time.kills.to_string(),
+                    play_time.deaths.to_string(),
+                    play_time.seconds.to_string(),
+                ],
+            );
+        }
+
+        let mut modes: Vec<_> = game.means_of_death.iter().collect();
+        modes.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.as_str().cmp(b.0.as_str())));
+        for (mode, count) in modes {
+            push_row(
+                &mut means_of_death,
+                &[i.to_string(), mode.as_str().to_string(), count.to_string()],
+            );
+        }
+
+        for kill in &game.kill_feed {
+            push_row(
+                &mut kills,
+                &[
+                    i.to_string(),
+                    kill.time.to_string(),
+                    kill.killer.clone(),
+                    kill.victim.clone(),
+                    kill.means_of_death.as_str().to_string(),
+                ],
+            );
+        }
+    }
+
+    vec![
+        Table {
+            name: "games",
+            content: games_table,
+        },
+        Table {
+            name: "players",
+            content: players,
+        },
+        Table {
+            name: "means_of_death",
+            content: means_of_death,
+        },
+        Table {
+            name: "kills",
+            content: kills,
+        },
+    ]
+}
+
+/// renders every table into a single document, each one under a `# name` header line
+pub fn render(games: &[Game]) -> String {
+    tables(games)
+        .iter()
+        .map(|table| format!("# {}\n{}", table.name, table.content))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn push_row(table: &mut String, fields: &[String]) {
+    let row: Vec<String> = fields.iter().map(|f| escape(f)).collect();
+    table.push_str(&row.join(","));
+    table.push('\n');
+}
+
+/// quotes fields containing separators, quotes or line breaks as described on RFC 4180
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::actions::Action;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("Zeh"), "Zeh");
+        assert_eq!(escape("a, b"), "\"a, b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_tables() -> Result<(), &'static str> {
+        let mut game = Game::new();
+        for (id, name) in [(1, "Dono, da Bola"), (2, "Zeh")] {
+            game.new_player(id);
+            game.rename_player(id, name.to_string())?;
+            game.player_joined(id)?;
+        }
+        game.clock = 65;
+        Action::Kill(2, 1, 10).parse(&mut game)?;
+        Action::Kill(crate::parser::game::WORLD, 2, 22).parse(&mut game)?;
+
+        let tables = tables(&[game]);
+        assert_eq!(
+            tables[0].content,
+            "game,total_kills,players,first_blood\n0,2,2,Zeh\n"
+        );
+        assert_eq!(
+            tables[1].content,
+            "game,player,score,kills,deaths,play_time\n0,\"Dono, da Bola\",0,0,1,0\n0,Zeh,0,1,1,0\n"
+        );
+        assert_eq!(
+            tables[2].content,
+            "game,means_of_death,count\n0,MOD_RAILGUN,1\n0,MOD_TRIGGER_HURT,1\n"
+        );
+        assert_eq!(
+            tables[3].content,
+            "game,time,killer,victim,means_of_death\n0,65,Zeh,\"Dono, da Bola\",MOD_RAILGUN\n0,65,<world>,Zeh,MOD_TRIGGER_HURT\n"
+        );
+        Ok(())
+    }
+}
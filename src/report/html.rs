@@ -0,0 +1,213 @@
+use crate::parser::game::Game;
+use crate::parser::ranking::rank;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 960px; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; width: 100%; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }
+th { background: #eee; }
+table.sortable th { cursor: pointer; }
+section { border-top: 2px solid #444; margin-top: 2em; }
+.feed { max-height: 20em; overflow-y: auto; }
+";
+
+// sorts a table by the clicked column, numerically when possible. clicking again reverses it
+const SCRIPT: &str = "
+document.querySelectorAll('table.sortable th').forEach(function (th) {
+  th.addEventListener('click', function () {
+    var body = th.closest('table').tBodies[0];
+    var descending = th.dataset.order !== 'desc';
+    th.dataset.order = descending ? 'desc' : 'asc';
+    var value = function (row) {
+      var text = row.cells[th.cellIndex].textContent;
+      var number = parseFloat(text);
+      return isNaN(number) ? text.toLowerCase() : number;
+    };
+    Array.from(body.rows)
+      .sort(function (a, b) {
+        var x = value(a), y = value(b);
+        var order = x < y ? -1 : x > y ? 1 : 0;
+        return descending ? -order : order;
+      })
+      .forEach(function (row) { body.appendChild(row); });
+  });
+});
+";
+
+/// renders a self-contained HTML page with an index of the games, a section for each one and the
+/// overall ranking
+pub fn render(games: &[Game]) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Quake 3 Arena match report</title>\n");
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    html.push_str("<h1>Quake 3 Arena match report</h1>\n");
+
+    html.push_str("<h2>Games</h2>\n<table class=\"sortable\">\n");
+    html.push_str("<thead><tr><th>Game</th><th>Map</th><th>Duration</th><th>Kills</th><th>Winner</th></tr></thead>\n<tbody>\n");
+    for (i, game) in games.iter().enumerate() {
+        html.push_str(&format!(
+            "<tr><td><a href=\"#game_{i}\">game_{i}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape(game.map().unwrap_or("-")),
+            format_time(game.duration()),
+            game.total_kills,
+            escape(&game.winner().unwrap_or_else(|| "-".to_string())),
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+    html.push_str("<p><a href=\"#ranking\">Overall ranking</a></p>\n");
+
+    for (i, game) in games.iter().enumerate() {
+        html.push_str(&render_game(i, game));
+    }
+
+    html.push_str(&render_ranking(games));
+    html.push_str(&format!("<script>{}</script>\n</body>\n</html>\n", SCRIPT));
+    html
+}
+
+fn render_game(index: usize, game: &Game) -> String {
+    let mut html = format!(
+        "<section id=\"game_{index}\">\n<h2>game_{index} &mdash; {}</h2>\n",
+        escape(game.map().unwrap_or("unknown map"))
+    );
+    html.push_str(&format!(
+        "<p>Duration {}, {} kills",
+        format_time(game.duration()),
+        game.total_kills
+    ));
+    if let Some(first_blood) = &game.first_blood {
+        html.push_str(&format!(
+            ", first blood by {} on {}",
+            escape(&first_blood.killer),
+            escape(&first_blood.victim)
+        ));
+    }
+    html.push_str(".</p>\n");
+
+    html.push_str("<h3>Scoreboard</h3>\n<table class=\"sortable\">\n");
+    html.push_str("<thead><tr><th>Player</th><th>Score</th><th>Kills</th><th>Deaths</th><th>Longest streak</th><th>Play time</th><th>Kills/min</th></tr></thead>\n<tbody>\n");
+    let mut names = game.player_list.clone();
+    names.sort();
+    names.dedup();
+    names.sort_by_key(|name| std::cmp::Reverse(*game.kill_score.get(name).unwrap_or(&0)));
+    for name in &names {
+        let play_time = game.play_time.get(name).cloned().unwrap_or_default();
+        let streak = game.streaks.get(name).map_or(0, |s| s.longest_streak);
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.2}</td></tr>\n",
+            escape(name),
+            game.kill_score.get(name).unwrap_or(&0),
+            play_time.kills,
+            play_time.deaths,
+            streak,
+            format_time(play_time.seconds),
+            play_time.kills_per_minute(),
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    html.push_str("<h3>Means of death</h3>\n<table class=\"sortable\">\n");
+    html.push_str("<thead><tr><th>Means of death</th><th>Count</th></tr></thead>\n<tbody>\n");
+    let mut modes: Vec<_> = game.means_of_death.iter().collect();
+    modes.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.as_str().cmp(b.0.as_str())));
+    for (mode, count) in modes {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td></tr>\n",
+            mode.as_str(),
+            count
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    html.push_str("<h3>Kill feed</h3>\n<div class=\"feed\">\n<table>\n");
+    html.push_str("<thead><tr><th>Time</th><th>Killer</th><th>Victim</th><th>Means of death</th></tr></thead>\n<tbody>\n");
+    for kill in &game.kill_feed {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            format_time(kill.time),
+            escape(&kill.killer),
+            escape(&kill.victim),
+            kill.means_of_death.as_str()
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n</div>\n</section>\n");
+    html
+}
+
+fn render_ranking(games: &[Game]) -> String {
+    let mut html = String::from(
+        "<section id=\"ranking\">\n<h2>Overall ranking</h2>\n<table class=\"sortable\">\n",
+    );
+    html.push_str("<thead><tr><th>#</th><th>Player</th><th>Games</th><th>Score</th><th>Kills/min</th><th>Longest streak</th><th>First bloods</th><th>Excellent</th><th>Impressive</th><th>Gauntlet</th></tr></thead>\n<tbody>\n");
+    for (i, player) in rank(games).iter().enumerate() {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.2}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            i + 1,
+            escape(&player.name),
+            player.games,
+            player.kills,
+            player.play_time.kills_per_minute(),
+            player.longest_streak,
+            player.first_bloods,
+            player.excellent,
+            player.impressive,
+            player.gauntlet,
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n</section>\n");
+    html
+}
+
+/// formats seconds as `minutes:seconds`, the same way the log prints them
+fn format_time(seconds: u32) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::actions::Action;
+
+    #[test]
+    fn test_escape_and_time() {
+        assert_eq!(
+            escape("<world> & \"friends\""),
+            "&lt;world&gt; &amp; &quot;friends&quot;"
+        );
+        assert_eq!(format_time(68), "1:08");
+        assert_eq!(format_time(58899), "981:39");
+    }
+
+    #[test]
+    fn test_render() -> Result<(), &'static str> {
+        let mut game = Game::new();
+        game.set_settings("\\mapname\\q3dm17");
+        for (id, name) in [(1, "<b>A</b>"), (2, "B")] {
+            game.new_player(id);
+            game.rename_player(id, name.to_string())?;
+            game.player_joined(id)?;
+        }
+        Action::Kill(2, 1, 10).parse(&mut game)?;
+        Action::Kill(crate::parser::game::WORLD, 2, 22).parse(&mut game)?;
+
+        let html = render(&[game]);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<a href=\"#game_0\">game_0</a></td><td>q3dm17</td>"));
+        assert!(html.contains("<section id=\"game_0\">"));
+        assert!(
+            html.contains("<td>0:00</td><td>&lt;world&gt;</td><td>B</td><td>MOD_TRIGGER_HURT</td>")
+        );
+        assert!(html.contains("<section id=\"ranking\">"));
+        assert!(!html.contains("<b>A</b>"));
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+//! versioned JSON reports that can be archived and loaded back later
+//!
+//! a report is written as `{"version": 1, "games": [...]}`. the version is bumped whenever a change
+//! to [Game] makes older readers misread a report. the loader also accepts the unversioned map of
+//! games (`{"game_0": {...}}`) printed by the default `json` format.
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::game::Game;
+use crate::parser::ranking::{rank, PlayerRanking};
+
+/// version of the report schema written by this build
+pub const REPORT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Report {
+    pub version: u32,
+    pub games: Vec<Game>,
+}
+
+impl Report {
+    pub fn new(games: Vec<Game>) -> Report {
+        Report {
+            version: REPORT_VERSION,
+            games,
+        }
+    }
+
+    /// parses a report, either versioned or the map of games printed by the `json` format
+    pub fn from_json(content: &str) -> Result<Report, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| format!("invalid report: {}", e))?;
+
+        let version = match value.get("version") {
+            Some(version) => version
+                .as_u64()
+                .ok_or("invalid report: version is not a number")?,
+            None => return from_game_map(value),
+        };
+        if version > REPORT_VERSION as u64 {
+            return Err(format!(
+                "unsupported report version {} (newest supported is {})",
+                version, REPORT_VERSION
+            ));
+        }
+        serde_json::from_value(value).map_err(|e| format!("invalid report: {}", e))
+    }
+
+    /// reads a report written by a previous run
+    pub fn load(path: &str) -> Result<Report, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return Err(format!("could not read report {}: {}", path, e)),
+        };
+        Report::from_json(&content).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+/// converts the `{"game_N": {...}}` map back into a list ordered by game index
+fn from_game_map(value: serde_json::Value) -> Result<Report, String> {
+    let map: HashMap<String, Game> =
+        serde_json::from_value(value).map_err(|e| format!("invalid report: {}", e))?;
+
+    let mut games = Vec::with_capacity(map.len());
+    for (key, game) in map {
+        let index = key
+            .strip_prefix("game_")
+            .and_then(|index| index.parse::<usize>().ok())
+            .ok_or_else(|| format!("invalid report: unexpected key {}", key))?;
+        games.push((index, game));
+    }
+    games.sort_by_key(|(index, _)| *index);
+
+    Ok(Report::new(
+        games.into_iter().map(|(_, game)| game).collect(),
+    ))
+}
+
+/// combines the games of every report into a single ranking
+///
+/// reports may overlap, such as the same log parsed twice, so a game shows up only once no matter
+/// how many reports have it. games are the same when everything reported about them matches,
+/// including the lines they were read from and the fingerprint of the whole log. games without a
+/// log fingerprint can't be told apart from a game that merely played out the same, so they're
+/// always counted
+pub fn merge(reports: &[Report]) -> Vec<PlayerRanking> {
+    let mut seen = HashSet::new();
+    let games: Vec<Game> = reports
+        .iter()
+        .flat_map(|report| report.games.iter())
+        .filter(|game| {
+            let identified = game
+                .source
+                .as_ref()
+                .is_some_and(|s| s.fingerprint.is_some());
+            !identified || seen.insert(fingerprint(game))
+        })
+        .cloned()
+        .collect();
+    rank(&games)
+}
+
+/// the game as reported. JSON objects are sorted by key, so equal games give equal strings
+fn fingerprint(game: &Game) -> String {
+    serde_json::to_value(game)
+        .expect("games are representable as JSON")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::parser::scoring::ScoringRules;
+
+    fn sample_games() -> Vec<Game> {
+        let lines = vec![
+            "  0:00 InitGame: \\mapname\\q3dm17".to_string(),
+            "  0:01 ClientConnect: 2".to_string(),
+            "  0:01 ClientUserinfoChanged: 2 n\\Isgalamido\\t\\0".to_string(),
+            "  0:01 ClientBegin: 2".to_string(),
+            "  0:02 ClientConnect: 3".to_string(),
+            "  0:02 ClientUserinfoChanged: 3 n\\Zeh\\t\\0".to_string(),
+            "  0:02 ClientBegin: 3".to_string(),
+            "  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN".to_string(),
+            "  0:11 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN".to_string(),
+            "  0:40 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT".to_string(),
+            "  1:00 ShutdownGame:".to_string(),
+            "  1:00 InitGame: \\mapname\\q3dm6".to_string(),
+            "  1:01 ClientConnect: 3".to_string(),
+            "  1:01 ClientUserinfoChanged: 3 n\\Zeh\\t\\0".to_string(),
+            "  1:01 ClientBegin: 3".to_string(),
+            "  1:30 ShutdownGame:".to_string(),
+        ];
+        parse(lines, &ScoringRules::default()).unwrap()
+    }
+
+    /// games as they look after a round trip: everything the report doesn't carry is left empty
+    fn reported(games: &[Game]) -> Vec<Game> {
+        let json = serde_json::to_string(games).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let games = sample_games();
+        let json = serde_json::to_string(&Report::new(games.clone())).unwrap();
+        let report = Report::from_json(&json).unwrap();
+
+        assert_eq!(report.version, REPORT_VERSION);
+        assert_eq!(report.games, reported(&games));
+        assert_eq!(report.games[0].kill_score, games[0].kill_score);
+        assert_eq!(report.games[0].means_of_death, games[0].means_of_death);
+        assert_eq!(report.games[0].play_time, games[0].play_time);
+    }
+
+    #[test]
+    fn test_load_game_map() {
+        let games = sample_games();
+        let mut map = HashMap::new();
+        for (i, game) in games.iter().enumerate() {
+            map.insert(format!("game_{}", i), game);
+        }
+        let report = Report::from_json(&serde_json::to_string(&map).unwrap()).unwrap();
+
+        assert_eq!(report.version, REPORT_VERSION);
+        assert_eq!(report.games, reported(&games));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        assert_eq!(
+            Report::from_json("{\"version\":99,\"games\":[]}"),
+            Err("unsupported report version 99 (newest supported is 1)".to_string())
+        );
+        assert!(Report::from_json("{\"game_x\":{}}").is_err());
+        assert!(Report::from_json("not json").is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let games = sample_games();
+        let first = Report::new(vec![games[0].clone()]);
+        let second = Report::from_json(
+            &serde_json::to_string(&Report::new(vec![games[1].clone()])).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(merge(&[first, second]), rank(&games));
+    }
+
+    /// games as tagged when read from a log with the given lines
+    fn read_from(mut games: Vec<Game>, file: Option<&str>, log: &[&str]) -> Vec<Game> {
+        let log: Vec<String> = log.iter().map(|line| line.to_string()).collect();
+        for source in games.iter_mut().filter_map(|game| game.source.as_mut()) {
+            source.file = file.map(String::from);
+            source.fingerprint = Some(crate::input::fingerprint(&log));
+        }
+        games
+    }
+
+    #[test]
+    fn test_merge_overlapping_reports() {
+        let games = read_from(sample_games(), Some("games.log"), &["monday"]);
+        let report = Report::new(games.clone());
+        let loaded = Report::from_json(&serde_json::to_string(&report).unwrap()).unwrap();
+
+        assert_eq!(merge(&[report.clone(), report.clone()]), rank(&games));
+        assert_eq!(merge(&[report, loaded]), rank(&games));
+    }
+
+    #[test]
+    fn test_merge_distinct_logs() {
+        let games = sample_games();
+        // the same games on two different nights, both read from stdin or both from games.log
+        let night =
+            |file: Option<&str>, log: &str| Report::new(read_from(games.clone(), file, &[log]));
+        // reports with no fingerprint to tell them apart
+        let untagged = Report::new(games.clone());
+
+        let twice = rank(&[games.clone(), games.clone()].concat());
+        assert_eq!(
+            merge(&[night(None, "monday"), night(None, "tuesday")]),
+            twice
+        );
+        assert_eq!(
+            merge(&[
+                night(Some("games.log"), "monday"),
+                night(Some("games.log"), "tuesday")
+            ]),
+            twice
+        );
+        assert_eq!(merge(&[untagged.clone(), untagged]), twice);
+    }
+}
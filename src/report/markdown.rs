@@ -0,0 +1,121 @@
+use crate::parser::game::Game;
+use crate::parser::ranking::rank;
+
+/// renders GitHub flavoured markdown with a players and a means of death table for each game,
+/// followed by the ranking across all games
+///
+/// # Example
+/// ```
+/// use q3a_log_parser::{report, Game};
+///
+/// let md = report::markdown::render(&[Game::new()]);
+/// assert!(md.starts_with("## game_0\n\nTotal kills: 0"));
+/// ```
+pub fn render(games: &[Game]) -> String {
+    let mut md = String::new();
+
+    for (i, game) in games.iter().enumerate() {
+        md.push_str(&format!("## game_{}", i));
+        if let Some(map) = game.map() {
+            md.push_str(&format!(" ({})", escape(map)));
+        }
+        md.push_str(&format!("\n\nTotal kills: {}\n\n", game.total_kills));
+
+        md.push_str("| Player | Kills |\n|---|---:|\n");
+        let mut names = game.player_list.clone();
+        names.sort();
+        names.dedup();
+        names.sort_by_key(|name| std::cmp::Reverse(*game.kill_score.get(name).unwrap_or(&0)));
+        for name in &names {
+            md.push_str(&format!(
+                "| {} | {} |\n",
+                escape(name),
+                game.kill_score.get(name).unwrap_or(&0)
+            ));
+        }
+
+        if !game.means_of_death.is_empty() {
+            md.push_str("\n| Means of death | Count |\n|---|---:|\n");
+            let mut modes: Vec<_> = game.means_of_death.iter().collect();
+            modes.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.as_str().cmp(b.0.as_str())));
+            for (mode, count) in modes {
+                md.push_str(&format!("| {} | {} |\n", mode.as_str(), count));
+            }
+        }
+        md.push('\n');
+    }
+
+    md.push_str("## Ranking\n\n| # | Player | Games | Kills |\n|---:|---|---:|---:|\n");
+    for (i, player) in rank(games).iter().enumerate() {
+        md.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            i + 1,
+            escape(&player.name),
+            player.games,
+            player.kills
+        ));
+    }
+    md
+}
+
+/// escapes characters that would break a table cell or be read as formatting
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '|' | '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::actions::Action;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("Dono da Bola"), "Dono da Bola");
+        assert_eq!(escape("x|_Zeh_|x"), "x\\|\\_Zeh\\_\\|x");
+    }
+
+    #[test]
+    fn test_render() -> Result<(), &'static str> {
+        let mut game = Game::new();
+        game.set_settings("\\mapname\\q3dm17");
+        for (id, name) in [(1, "Isgalamido"), (2, "Zeh")] {
+            game.new_player(id);
+            game.rename_player(id, name.to_string())?;
+            game.player_joined(id)?;
+        }
+        Action::Kill(2, 1, 10).parse(&mut game)?;
+        Action::Kill(2, 1, 10).parse(&mut game)?;
+        Action::Kill(crate::parser::game::WORLD, 1, 22).parse(&mut game)?;
+
+        let expected = "## game_0 (q3dm17)
+
+Total kills: 3
+
+| Player | Kills |
+|---|---:|
+| Zeh | 2 |
+| Isgalamido | -1 |
+
+| Means of death | Count |
+|---|---:|
+| MOD_RAILGUN | 2 |
+| MOD_TRIGGER_HURT | 1 |
+
+## Ranking
+
+| # | Player | Games | Kills |
+|---:|---|---:|---:|
+| 1 | Zeh | 1 | 2 |
+| 2 | Isgalamido | 1 | -1 |
+";
+        assert_eq!(render(&[game]), expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+pub mod csv;
+pub mod html;
+pub mod json;
+pub mod markdown;
+pub mod ndjson;
+pub mod sqlite;
+pub mod stats;
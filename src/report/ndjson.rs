@@ -0,0 +1,154 @@
+use serde::Serialize;
+
+use crate::parser::actions::{Action, Event};
+use crate::parser::game::{Game, KillMode, WORLD};
+use crate::parser::scoring::ScoringRules;
+
+/// a single line of the event stream. the action is flattened into `type` and `data` fields and
+/// the ids it mentions are resolved into the names the players had at that moment
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct EventRecord<'a> {
+    pub game: usize,
+    pub time: u32,
+    pub line: usize,
+    #[serde(flatten)]
+    pub action: &'a Action,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub player: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub killer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub victim: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub means_of_death: Option<&'static str>,
+}
+
+/// renders one game report per line, tagged with the game index
+pub fn games(games: &[Game]) -> String {
+    #[derive(Serialize)]
+    struct GameLine<'a> {
+        game: usize,
+        #[serde(flatten)]
+        report: &'a Game,
+    }
+
+    games
+        .iter()
+        .enumerate()
+        .map(|(game, report)| serde_json::to_string(&GameLine { game, report }).unwrap() + "\n")
+        .collect()
+}
+
+/// renders one line per event. games are replayed while rendering so player names can be resolved
+pub fn events(grouped: &[Vec<Event>], scoring: &ScoringRules) -> Result<String, &'static str> {
+    // players only get a name once their userinfo arrives
+    let name = |game: &Game, id: u32| {
+        game.player_name(id)
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string())
+    };
+
+    let mut out = String::new();
+    for (i, events) in grouped.iter().enumerate() {
+        let mut game = Game::with_scoring(scoring.clone());
+        for event in events {
+            game.clock = event.time;
+            let mut record = EventRecord {
+                game: i,
+                time: event.time,
+                line: event.line,
+                action: &event.action,
+                player: None,
+                killer: None,
+                victim: None,
+                means_of_death: None,
+            };
+
+            // kills are resolved before being applied, everything else after so renames show
+            // the new name
+            if let Action::Kill(killer, killed, means_of_death) = &event.action {
+                record.killer = match *killer {
+                    WORLD => Some("<world>".to_string()),
+                    id => name(&game, id),
+                };
+                record.victim = name(&game, *killed);
+                record.means_of_death = Some(KillMode::from_u32(*means_of_death).as_str());
+            }
+            event.action.parse(&mut game)?;
+            match &event.action {
+                Action::ClientConnect(id)
+                | Action::ClientBegin(id)
+                | Action::ClientUserinfoChanged(id, _)
+                | Action::ClientDisconnect(id)
+                | Action::Item(id, _) => {
+                    record.player = name(&game, *id);
+                }
+                Action::Say(player, _)
+                | Action::SayTeam(player, _)
+                | Action::Tell(player, _, _) => record.player = Some(player.clone()),
+                _ => (),
+            }
+
+            out.push_str(&serde_json::to_string(&record).unwrap());
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_events;
+
+    #[test]
+    fn test_events() {
+        let lines = vec![
+            "  0:00 InitGame: \\mapname\\q3dm17".to_string(),
+            "  0:01 ClientConnect: 2".to_string(),
+            "  0:01 ClientUserinfoChanged: 2 n\\Isgalamido\\t\\0".to_string(),
+            "  0:02 ClientConnect: 3".to_string(),
+            "  0:02 ClientUserinfoChanged: 3 n\\Zeh\\t\\0".to_string(),
+            "  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN".to_string(),
+            "  0:11 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT".to_string(),
+            "  0:12 ShutdownGame:".to_string(),
+        ];
+
+        let out = events(&parse_events(lines), &ScoringRules::default()).unwrap();
+        let out: Vec<&str> = out.lines().collect();
+        assert_eq!(out.len(), 8);
+        assert_eq!(
+            out[0],
+            r#"{"game":0,"time":0,"line":1,"type":"init_game","data":"\\mapname\\q3dm17"}"#
+        );
+        assert_eq!(
+            out[1],
+            r#"{"game":0,"time":1,"line":2,"type":"client_connect","data":2}"#
+        );
+        assert_eq!(
+            out[2],
+            r#"{"game":0,"time":1,"line":3,"type":"client_userinfo_changed","data":[2,"n\\Isgalamido\\t\\0"],"player":"Isgalamido"}"#
+        );
+        assert_eq!(
+            out[5],
+            r#"{"game":0,"time":10,"line":6,"type":"kill","data":[2,3,10],"killer":"Isgalamido","victim":"Zeh","means_of_death":"MOD_RAILGUN"}"#
+        );
+        assert_eq!(
+            out[6],
+            r#"{"game":0,"time":11,"line":7,"type":"kill","data":[1022,2,22],"killer":"<world>","victim":"Isgalamido","means_of_death":"MOD_TRIGGER_HURT"}"#
+        );
+        assert_eq!(
+            out[7],
+            r#"{"game":0,"time":12,"line":8,"type":"shutdown_game"}"#
+        );
+    }
+
+    #[test]
+    fn test_games() {
+        let out = games(&[Game::new(), Game::new()]);
+        assert_eq!(
+            out,
+            "{\"game\":0,\"total_kills\":0,\"players\":[],\"kills\":{},\"means_of_death\":{}}\n{\"game\":1,\"total_kills\":0,\"players\":[],\"kills\":{},\"means_of_death\":{}}\n"
+        );
+    }
+}
@@ -0,0 +1,378 @@
+//! exports parsed games into an SQLite database
+//!
+//! the database is created if it doesn't exist, otherwise new games are appended to it. each game
+//! is identified by a fingerprint of its events, so importing the same log twice won't duplicate
+//! any game. the schema is:
+//!
+//! | table      | columns                                                          |
+//! |------------|------------------------------------------------------------------|
+//! | `games`    | `id`, `fingerprint` (unique), `map`, `total_kills`, `first_blood` |
+//! | `settings` | `game_id`, `key`, `value`                                        |
+//! | `players`  | `game_id`, `name`, `score`, `kills`, `deaths`, `play_time`       |
+//! | `sessions` | `game_id`, `player`, `start`, `end`                              |
+//! | `kills`    | `game_id`, `time`, `killer`, `victim`, `means_of_death`          |
+//! | `items`    | `game_id`, `time`, `player`, `item`                              |
+//! | `chat`     | `game_id`, `time`, `player`, `message`, `channel`, `recipient`   |
+//!
+//! every `game_id` references `games.id` and times are in seconds as printed on the log. the
+//! chat `channel` is `all` for `say`, `team` for `sayteam` and `private` for `tell`, which is the
+//! only one with a `recipient`. databases created before chat had a channel get the new columns
+//! on their next export, with their messages left on `all`.
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::collections::BTreeMap;
+
+use crate::parser::game::Game;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS games (
+    id INTEGER PRIMARY KEY,
+    fingerprint TEXT NOT NULL UNIQUE,
+    map TEXT,
+    total_kills INTEGER NOT NULL,
+    first_blood TEXT
+);
+CREATE TABLE IF NOT EXISTS settings (
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (game_id, key)
+);
+CREATE TABLE IF NOT EXISTS players (
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    name TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    kills INTEGER NOT NULL,
+    deaths INTEGER NOT NULL,
+    play_time INTEGER NOT NULL,
+    PRIMARY KEY (game_id, name)
+);
+CREATE TABLE IF NOT EXISTS sessions (
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    player TEXT NOT NULL,
+    start INTEGER NOT NULL,
+    end INTEGER
+);
+CREATE TABLE IF NOT EXISTS kills (
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    time INTEGER NOT NULL,
+    killer TEXT NOT NULL,
+    victim TEXT NOT NULL,
+    means_of_death TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS items (
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    time INTEGER NOT NULL,
+    player TEXT NOT NULL,
+    item TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS chat (
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    time INTEGER NOT NULL,
+    player TEXT NOT NULL,
+    message TEXT NOT NULL,
+    channel TEXT NOT NULL DEFAULT 'all',
+    recipient TEXT
+);
+";
+
+/// columns added to a table after it was first created, as `(table, column, definition)`
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("chat", "channel", "TEXT NOT NULL DEFAULT 'all'"),
+    ("chat", "recipient", "TEXT"),
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub imported: usize,
+    /// games that were already on the database
+    pub skipped: usize,
+}
+
+/// writes the games into the database at `path`, skipping the ones imported before
+pub fn export(path: &str, games: &[Game]) -> Result<ExportSummary, String> {
+    let mut conn = Connection::open(path).map_err(|e| format!("could not open database: {}", e))?;
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| format!("could not create schema: {}", e))?;
+    add_columns(&conn).map_err(|e| format!("could not update schema: {}", e))?;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("could not start transaction: {}", e))?;
+    let mut summary = ExportSummary::default();
+    for game in games {
+        if insert_game(&tx, game).map_err(|e| format!("could not insert game: {}", e))? {
+            summary.imported += 1;
+        } else {
+            summary.skipped += 1;
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("could not commit games: {}", e))?;
+
+    Ok(summary)
+}
+
+/// adds the [ADDED_COLUMNS] missing on a database created by an older version
+fn add_columns(conn: &Connection) -> rusqlite::Result<()> {
+    for (table, column, definition) in ADDED_COLUMNS {
+        let found: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+            params![table, column],
+            |row| row.get(0),
+        )?;
+        if found == 0 {
+            conn.execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// inserts a game and all of its rows. returns false if the game was already imported
+fn insert_game(tx: &Transaction, game: &Game) -> rusqlite::Result<bool> {
+    let fingerprint = fingerprint(game);
+    let existing: Option<i64> = tx
+        .query_row(
+            "SELECT id FROM games WHERE fingerprint = ?1",
+            params![fingerprint],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if existing.is_some() {
+        return Ok(false);
+    }
+
+    tx.execute(
+        "INSERT INTO games (fingerprint, map, total_kills, first_blood) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            fingerprint,
+            game.map(),
+            game.total_kills,
+            game.first_blood.as_ref().map(|f| f.killer.as_str())
+        ],
+    )?;
+    let id = tx.last_insert_rowid();
+
+    for (key, value) in &game.settings {
+        tx.execute(
+            "INSERT INTO settings (game_id, key, value) VALUES (?1, ?2, ?3)",
+            params![id, key, value],
+        )?;
+    }
+
+    let mut names: Vec<&String> = game
+        .player_list
+        .iter()
+        .chain(game.kill_score.keys())
+        .collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let play_time = game.play_time.get(name).cloned().unwrap_or_default();
+        tx.execute(
+            "INSERT INTO players (game_id, name, score, kills, deaths, play_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                id,
+                name,
+                game.kill_score.get(name).unwrap_or(&0),
+                play_time.kills,
+                play_time.deaths,
+                play_time.seconds
+            ],
+        )?;
+    }
+
+    for player in &game.players {
+        for session in &player.sessions {
+            tx.execute(
+                "INSERT INTO sessions (game_id, player, start, end) VALUES (?1, ?2, ?3, ?4)",
+                params![id, player.name, session.start, session.end],
+            )?;
+        }
+    }
+
+    for kill in &game.kill_feed {
+        tx.execute(
+            "INSERT INTO kills (game_id, time, killer, victim, means_of_death) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, kill.time, kill.killer, kill.victim, kill.means_of_death.as_str()],
+        )?;
+    }
+
+    for item in &game.items {
+        tx.execute(
+            "INSERT INTO items (game_id, time, player, item) VALUES (?1, ?2, ?3, ?4)",
+            params![id, item.time, item.player, item.item],
+        )?;
+    }
+
+    for message in &game.chat {
+        tx.execute(
+            "INSERT INTO chat (game_id, time, player, message, channel, recipient) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                id,
+                message.time,
+                message.player,
+                message.message,
+                message.channel.as_str(),
+                message.recipient
+            ],
+        )?;
+    }
+
+    Ok(true)
+}
+
+/// FNV-1a hash of everything that happened in the game. unlike the std hasher it is stable
+/// between builds, so it can be persisted
+fn fingerprint(game: &Game) -> String {
+    let settings: BTreeMap<&String, &String> = game.settings.iter().collect();
+    let content = serde_json::to_string(&(
+        settings,
+        &game.players,
+        &game.kill_feed,
+        &game.items,
+        &game.chat,
+    ))
+    .unwrap();
+
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in content.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::actions::Action;
+
+    fn sample_game() -> Result<Game, &'static str> {
+        let mut game = Game::new();
+        game.set_settings("\\mapname\\q3dm17\\sv_hostname\\Code Miner Server");
+        for (id, name) in [(1, "A"), (2, "B")] {
+            game.new_player(id);
+            game.rename_player(id, name.to_string())?;
+            game.player_joined(id)?;
+        }
+        game.clock = 10;
+        game.add_item(1, "weapon_railgun".to_string())?;
+        Action::Kill(1, 2, 10).parse(&mut game)?;
+        game.add_chat("B".to_string(), "gg".to_string());
+        game.add_team_chat("A".to_string(), "rush b".to_string());
+        game.add_private_chat("A".to_string(), "B".to_string(), "wp".to_string());
+        game.shutdown();
+        Ok(game)
+    }
+
+    #[test]
+    fn test_export() -> Result<(), &'static str> {
+        let path = "test_export.db";
+        let _ = std::fs::remove_file(path);
+        let game = sample_game()?;
+
+        let first = export(path, std::slice::from_ref(&game));
+        let second = export(path, &[game]);
+
+        let conn = Connection::open(path).unwrap();
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        let counts: Vec<i64> = [
+            "games", "settings", "players", "sessions", "kills", "items", "chat",
+        ]
+        .iter()
+        .map(|table| count(table))
+        .collect();
+        let map: String = conn
+            .query_row("SELECT map FROM games", [], |row| row.get(0))
+            .unwrap();
+        drop(conn);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            first,
+            Ok(ExportSummary {
+                imported: 1,
+                skipped: 0
+            })
+        );
+        assert_eq!(
+            second,
+            Ok(ExportSummary {
+                imported: 0,
+                skipped: 1
+            })
+        );
+        assert_eq!(counts, vec![1, 2, 2, 2, 1, 1, 3]);
+        assert_eq!(map, "q3dm17");
+        Ok(())
+    }
+
+    #[test]
+    fn test_fingerprint() -> Result<(), &'static str> {
+        let game = sample_game()?;
+        let mut other = game.clone();
+        other.add_chat("A".to_string(), "rematch?".to_string());
+
+        assert_eq!(fingerprint(&game), fingerprint(&game.clone()));
+        assert_ne!(fingerprint(&game), fingerprint(&other));
+        Ok(())
+    }
+
+    #[test]
+    fn test_chat_channels() -> Result<(), &'static str> {
+        let path = "test_chat_channels.db";
+        let _ = std::fs::remove_file(path);
+        // a database written before chat had a channel
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE chat (game_id INTEGER NOT NULL, time INTEGER NOT NULL, player TEXT NOT NULL, message TEXT NOT NULL);
+             INSERT INTO chat VALUES (0, 5, 'A', 'hi');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let result = export(path, &[sample_game()?]);
+        let conn = Connection::open(path).unwrap();
+        let mut statement = conn
+            .prepare("SELECT player, message, channel, recipient FROM chat ORDER BY rowid")
+            .unwrap();
+        let rows: Vec<(String, String, String, Option<String>)> = statement
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        drop(statement);
+        drop(conn);
+        std::fs::remove_file(path).unwrap();
+
+        assert!(result.is_ok());
+        let row = |player: &str, message: &str, channel: &str, recipient: Option<&str>| {
+            (
+                player.to_string(),
+                message.to_string(),
+                channel.to_string(),
+                recipient.map(String::from),
+            )
+        };
+        assert_eq!(
+            rows,
+            vec![
+                row("A", "hi", "all", None),
+                row("B", "gg", "all", None),
+                row("A", "rush b", "team", None),
+                row("A", "wp", "private", Some("B")),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::parser::game::Game;
+use crate::parser::line_type;
+
+/// a log level summary, covering every line read and not only the ones the parser understands
+#[derive(Clone, Debug, Default, Serialize, PartialEq, Eq)]
+pub struct LogStats {
+    pub lines: usize,
+    /// line counts by [line_type], including types the parser ignores
+    pub line_types: BTreeMap<String, usize>,
+    /// blank or truncated lines, with nothing after the timestamp
+    pub untyped_lines: usize,
+    pub games: usize,
+    pub total_kills: u32,
+    /// every named player that connected to any of the games, sorted by name
+    pub players: Vec<String>,
+}
+
+/// summarizes the raw lines of a log along with the games parsed from them
+pub fn summarize(lines: &[String], games: &[Game]) -> LogStats {
+    let mut stats = LogStats {
+        lines: lines.len(),
+        games: games.len(),
+        ..LogStats::default()
+    };
+
+    for line in lines {
+        match line_type(line) {
+            Some(kind) => *stats.line_types.entry(kind.to_string()).or_insert(0) += 1,
+            None => stats.untyped_lines += 1,
+        }
+    }
+
+    let mut players = BTreeSet::new();
+    for game in games {
+        stats.total_kills += game.total_kills;
+        players.extend(
+            game.players
+                .iter()
+                .filter(|player| !player.name.is_empty())
+                .map(|player| player.name.clone()),
+        );
+    }
+    stats.players = players.into_iter().collect();
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::parser::scoring::ScoringRules;
+
+    #[test]
+    fn test_summarize() {
+        let content = "  0:00 ------------------------------------------------------------
+  0:00 InitGame: \\mapname\\q3dm17
+  0:01 ClientConnect: 2
+  0:01 ClientUserinfoChanged: 2 n\\Isgalamido\\t\\0
+  0:02 ClientConnect: 3
+  0:02 ClientUserinfoChanged: 3 n\\Zeh\\t\\0
+  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN
+  0:11 Exit: Fraglimit hit.
+
+  0:20 ShutdownGame:
+  0:20 ------------------------------------------------------------
+  0:21 InitGame: \\mapname\\q3dm6
+  0:22 ClientConnect: 2
+  0:22 ClientUserinfoChanged: 2 n\\Isgalamido\\t\\0
+  0:25 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT
+  0:30 ShutdownGame:";
+        let lines: Vec<String> = content.lines().map(String::from).collect();
+        let games = parse(lines.clone(), &ScoringRules::default()).unwrap();
+
+        let stats = summarize(&lines, &games);
+        assert_eq!(stats.lines, 16);
+        assert_eq!(stats.untyped_lines, 1);
+        assert_eq!(stats.games, 2);
+        assert_eq!(stats.total_kills, 2);
+        assert_eq!(stats.players, vec!["Isgalamido", "Zeh"]);
+        assert_eq!(stats.line_types["ClientConnect"], 3);
+        assert_eq!(stats.line_types["Kill"], 2);
+        assert_eq!(stats.line_types["Exit"], 1);
+        assert_eq!(stats.line_types["separator"], 2);
+    }
+}